@@ -0,0 +1,130 @@
+// Turn what was typed in the Goto prompt into a URL: a full URL, a bare host, or a search.
+
+use servo::servo_url::ServoUrl;
+
+// Typed text starting with one of these is taken as a complete URL
+const SCHEMES:&[&str] = &["http", "https", "file", "about", "data", "ftp", "view-source"];
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Invalid, // Not an address, and no search template to fall back on
+    Unsupported(String), // An address with a scheme servo doesn't load, like mailto
+}
+
+/// Search is a template with %s for the query, or empty for no search
+pub fn parse(text: &str, search: &str) -> Result<ServoUrl, Error> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(Error::Invalid);
+    }
+
+    // Before parsing, since "localhost:8000" parses, with scheme "localhost"
+    if let Some(local) = host(text) {
+        let scheme = if local { "http" } else { "https" };
+        if let Ok(url) = ServoUrl::parse(&format!("{scheme}://{text}")) {
+            return Ok(url);
+        }
+    }
+    match ServoUrl::parse(text) {
+        Ok(url) if SCHEMES.contains(&url.scheme()) => return Ok(url),
+        // "define: word" parses too, but is more likely a search
+        Ok(url) if !text.contains(char::is_whitespace) => return Err(Error::Unsupported(url.scheme().to_string())),
+        _ => ()
+    }
+
+    search.contains("%s").then(|| ServoUrl::parse(&search.replace("%s", &encode(text))).ok()).flatten().ok_or(Error::Invalid)
+}
+
+// Does text look like host[:port][/path], with no scheme? Some(true) if the host is local
+// (localhost or an IP address, which likely don't have https), Some(false) for a domain name
+fn host(text: &str) -> Option<bool> {
+    let authority = text.split(['/', '?', '#']).next()?;
+    let host = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => host,
+        Some(_) => return None,
+        None => authority,
+    };
+    let labels:Vec<&str> = host.split('.').collect();
+    if !labels.iter().all(|label| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-')) {
+        return None;
+    }
+    let numeric = |label: &&str| label.bytes().all(|b| b.is_ascii_digit());
+    if host == "localhost" || (labels.len() == 4 && labels.iter().all(numeric)) {
+        Some(true)
+    } else if labels.len() > 1 && !labels.last().is_some_and(numeric) {
+        Some(false) // Not "3.14"
+    } else {
+        None
+    }
+}
+
+// Encode a search query for a URL, as forms do
+fn encode(text: &str) -> String {
+    text.bytes().map(|b| match b {
+        b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        b' ' => "+".to_string(),
+        _ => format!("%{b:02X}"),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEARCH:&str = "https://search.example/?q=%s";
+
+    fn url(text: &str) -> String {
+        parse(text, SEARCH).unwrap().as_str().to_string()
+    }
+
+    #[test]
+    fn bare_host() {
+        assert_eq!(url("example.com"), "https://example.com/");
+        assert_eq!(url("example.com/a/b?c=d"), "https://example.com/a/b?c=d");
+    }
+
+    #[test]
+    fn host_and_port() {
+        assert_eq!(url("example.com:8080"), "https://example.com:8080/");
+        assert_eq!(url("example.com:8080/x"), "https://example.com:8080/x");
+    }
+
+    #[test]
+    fn localhost() {
+        assert_eq!(url("localhost"), "http://localhost/");
+        assert_eq!(url("localhost:8000"), "http://localhost:8000/");
+        assert_eq!(url("localhost/page"), "http://localhost/page");
+    }
+
+    #[test]
+    fn ip_and_port() {
+        assert_eq!(url("127.0.0.1:8080"), "http://127.0.0.1:8080/");
+        assert_eq!(url("192.168.1.1"), "http://192.168.1.1/");
+    }
+
+    #[test]
+    fn full_url() {
+        assert_eq!(url("http://example.com/x"), "http://example.com/x");
+        assert_eq!(url("about:blank"), "about:blank");
+    }
+
+    #[test]
+    fn search_fallback() {
+        assert_eq!(url("hello world"), "https://search.example/?q=hello+world");
+        assert_eq!(url("3.14"), "https://search.example/?q=3.14");
+        assert_eq!(url("define: word"), "https://search.example/?q=define%3A+word");
+    }
+
+    #[test]
+    fn empty_search_template() {
+        assert_eq!(parse("hello world", ""), Err(Error::Invalid));
+        assert_eq!(parse("   ", SEARCH), Err(Error::Invalid));
+        assert_eq!(parse("example.com", "").unwrap().as_str(), "https://example.com/");
+    }
+
+    #[test]
+    fn other_schemes() {
+        assert_eq!(parse("mailto:a@example.com", SEARCH), Err(Error::Unsupported("mailto".to_string())));
+        assert_eq!(parse("wss://x.org", SEARCH), Err(Error::Unsupported("wss".to_string())));
+    }
+}
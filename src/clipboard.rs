@@ -0,0 +1,57 @@
+// Clipboard. OSC 52 asks the terminal to set the system clipboard, which works over ssh too.
+// Pastes ask it back the same way; many terminals won't say, so then they get whatever was last copied from here.
+
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{config, tty};
+
+pub struct Clipboard {
+    backend: config::Clipboard,
+    contents: String, // Last copied, for pasting back
+    reads: bool, // The terminal might answer OSC 52 reads. Off once it hasn't
+}
+
+impl Clipboard {
+    pub fn new(backend: config::Clipboard) -> Self {
+        Clipboard { backend, contents: String::new(), reads: backend == config::Clipboard::Osc52 }
+    }
+
+    /// Will get() ask the terminal? Then terminal input must be paused around it, so the reply comes here
+    pub fn asks_terminal(&self) -> bool {
+        self.reads
+    }
+
+    /// What to paste: the terminal's clipboard if it says, else the last copied
+    pub fn get(&mut self) -> &str {
+        if self.reads {
+            match tty::clipboard() {
+                Some(text) => self.contents = text,
+                None => self.reads = false,
+            }
+        }
+        &self.contents
+    }
+
+    /// Copy text. Kept here even if the terminal can't be told
+    pub fn set(&mut self, text: String) -> io::Result<()> {
+        self.contents = text;
+        match self.backend {
+            config::Clipboard::Osc52 => osc52(&self.contents),
+            config::Clipboard::Local => Ok(()),
+        }
+    }
+}
+
+fn osc52(text: &str) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    let sequence = format!("\x1b]52;c;{}\x07", BASE64.encode(text));
+    if std::env::var_os("TMUX").is_some() {
+        // tmux passes it on to the real terminal if wrapped like this (and allow-passthrough is on)
+        write!(out, "\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))?;
+    } else {
+        out.write_all(sequence.as_bytes())?;
+    }
+    out.flush()
+}
@@ -0,0 +1,78 @@
+// Settings. Each can be set from the environment as CUERVO_<NAME>, e.g. CUERVO_MAX_FPS=30.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where copied text goes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clipboard { Osc52, Local }
+
+impl FromStr for Clipboard {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "osc52" => Ok(Clipboard::Osc52),
+            "local" => Ok(Clipboard::Local),
+            _ => Err(())
+        }
+    }
+}
+
+/// What to do when a page opens a new window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Popups { Always, Ask, Never }
+
+impl FromStr for Popups {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s {
+            "always" => Ok(Popups::Always),
+            "ask" => Ok(Popups::Ask),
+            "never" => Ok(Popups::Never),
+            _ => Err(())
+        }
+    }
+}
+
+pub struct Config {
+    pub max_fps: f32, // Redraw limit while the page animates
+    pub images: Option<String>, // kitty, iterm, sixel or none; guessed if unset
+    pub popups: Popups, // Allow window.open and target=_blank? They become tabs
+    pub search: String, // URL to search with, %s is the query. Empty for no search
+    pub cell: Option<(u16, u16)>, // Cell size in screen pixels, as WIDTHxHEIGHT; asked of the terminal if unset
+    pub downloads: PathBuf, // Where saved links go
+    pub clipboard: Clipboard, // Tell the terminal (osc52), or only keep copies for pasting in pages (local)
+    pub uploads: PathBuf, // Where the file picker starts, made if missing. Giving pages files from outside it takes a yes
+    pub profile: PathBuf, // Where cuervo keeps things between runs
+    pub webdriver: bool, // Start servo's WebDriver server, which text view needs. Kept to loopback
+}
+
+fn var<T: FromStr>(name: &str) -> Option<T> {
+    std::env::var(format!("CUERVO_{name}")).ok()?.parse().ok()
+}
+
+impl Config {
+    pub fn load() -> Self {
+        Config {
+            max_fps: var("MAX_FPS").filter(|fps: &f32| *fps > 0.0).unwrap_or(15.0),
+            images: var("IMAGES"),
+            popups: var("POPUPS").unwrap_or(Popups::Ask),
+            search: var("SEARCH").unwrap_or_else(|| "https://html.duckduckgo.com/html/?q=%s".to_string()),
+            cell: var::<String>("CELL").and_then(|cell| {
+                let (width, height) = cell.split_once('x')?;
+                Some((width.parse().ok()?, height.parse().ok()?))
+            }).filter(|&(width, height)| width > 0 && height > 0),
+            downloads: var("DOWNLOADS").unwrap_or_else(|| std::env::var_os("HOME")
+                .map_or_else(|| PathBuf::from("."), |home| PathBuf::from(home).join("Downloads"))),
+            clipboard: var("CLIPBOARD").unwrap_or(Clipboard::Osc52),
+            uploads: var("UPLOADS").unwrap_or_else(|| std::env::var_os("HOME")
+                .map_or_else(|| PathBuf::from("Uploads"), |home| PathBuf::from(home).join("Uploads"))),
+            profile: var("PROFILE").unwrap_or_else(|| match (std::env::var_os("XDG_DATA_HOME"), std::env::var_os("HOME")) {
+                (Some(data), _) => PathBuf::from(data).join("cuervo"),
+                (None, Some(home)) => PathBuf::from(home).join(".local/share/cuervo"),
+                (None, None) => PathBuf::from(".cuervo"),
+            }),
+            webdriver: var("WEBDRIVER").unwrap_or(true),
+        }
+    }
+}
@@ -0,0 +1,303 @@
+// Downloads. Each is fetched on a thread of its own and written to disk as it arrives; trying again carries on
+// from what's on disk with a Range request. A server that says no (4xx) may want the cookies of the page that
+// linked there, which servo keeps to itself, so then the page fetches it instead (scripts/fetch.js), a chunk at
+// a time. That only works for files the page could fetch itself (same origin, or allowed by CORS), and only
+// while its tab is shown.
+// The list is kept in the profile folder as downloads.json.
+
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use hyper::body::HttpBody;
+use hyper::header::{self, HeaderName};
+use hyper::{Body, Client, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use servo::servo_url::ServoUrl;
+use servo::TopLevelBrowsingContextId;
+
+pub const CHUNK:u64 = 1 << 20; // Bytes the page asks for at a time
+const LIST:&str = "downloads.json";
+const REDIRECTS:usize = 10; // Most followed
+const PROGRESS:Duration = Duration::from_millis(250); // Least time between progress updates
+const POLL:Duration = Duration::from_secs(1); // How often to check for being stopped while the server is quiet
+const STALL:Duration = Duration::from_secs(30); // Most time the server can be quiet before giving up
+
+// Links to these are saved rather than opened
+const EXTENSIONS:&[&str] = &[
+    "zip", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "tar", "pdf", "epub", "exe", "msi", "dmg", "pkg",
+    "deb", "rpm", "apk", "iso", "img", "bin", "docx", "xlsx", "pptx", "odt", "ods", "mp3", "flac", "ogg", "mp4", "mkv", "webm",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum State { Active, Done, Failed(String), Cancelled }
+
+/// What a download's thread says. Sent along with the download's id and attempt
+pub enum Update {
+    Progress(u64, Option<u64>), // Received, and total if the server said
+    Done,
+    Failed(String),
+    Refused(String), // The server said no, perhaps for want of the page's cookies
+}
+
+pub struct Download {
+    pub id: usize, // Stays the same while others come and go
+    pub url: ServoUrl,
+    pub path: PathBuf,
+    pub received: u64, // Bytes written so far
+    pub total: Option<u64>, // If the server said
+    pub state: State,
+    pub tab: Option<TopLevelBrowsingContextId>, // Page that linked here, to fetch it if we can't. Not kept between runs
+    pub page: bool, // The page is fetching it
+    pub fetching: bool, // Page: a chunk is on its way
+    pub attempt: usize, // Counts starts, so updates from a thread given up on are told apart
+    stop: Arc<AtomicBool>, // Tells the current thread to give up
+}
+
+impl Download {
+    pub fn new(id: usize, url: ServoUrl, path: PathBuf, tab: TopLevelBrowsingContextId) -> Self {
+        Download {
+            id, url, path, received: 0, total: None, state: State::Active, tab: Some(tab), page: false, fetching: false,
+            attempt: 0, stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Fetch from `received` on, on a thread of its own. `notify` is called there with id, attempt and each update
+    pub fn start(&mut self, user_agent: String, notify: impl Fn(usize, usize, Update) + Send + 'static) {
+        self.stop.store(true, Ordering::SeqCst); // Any earlier thread
+        self.stop = Arc::new(AtomicBool::new(false));
+        self.attempt += 1;
+        self.state = State::Active;
+        self.page = false;
+        self.fetching = false;
+
+        let (id, attempt, stop) = (self.id, self.attempt, self.stop.clone());
+        let (url, path, from) = (self.url.clone(), self.path.clone(), self.received);
+        std::thread::spawn(move || {
+            let progress = |received, total| notify(id, attempt, Update::Progress(received, total));
+            let update = match fetch(&url, &path, from, &user_agent, &stop, progress) {
+                Ok(true) => Update::Done,
+                Ok(false) => return, // Stopped
+                Err(Error::Refused(e)) => Update::Refused(e),
+                Err(Error::Failed(e)) => Update::Failed(e),
+            };
+            notify(id, attempt, update);
+        });
+    }
+
+    /// Start again after failing or being cancelled, from whatever made it to disk
+    pub fn retry(&mut self, tab: TopLevelBrowsingContextId, user_agent: String, notify: impl Fn(usize, usize, Update) + Send + 'static) {
+        self.received = fs::metadata(&self.path).map_or(0, |meta| meta.len());
+        self.tab = Some(tab);
+        self.start(user_agent, notify);
+    }
+
+    pub fn cancel(&mut self) {
+        self.state = State::Cancelled;
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Arguments for fetch.js, for the next chunk
+    pub fn next_chunk(&self) -> Vec<Value> {
+        vec![self.url.as_str().into(), self.received.into(), CHUNK.into()]
+    }
+
+    /// Take the result of fetch.js. Finishes the download if that was the last of it
+    pub fn receive(&mut self, data: &[u8], total: Option<u64>, whole: bool) -> io::Result<()> {
+        // A server that ignores ranges sends it all at once
+        let mut file = if whole || self.received == 0 {
+            self.received = 0;
+            create(&self.path)?
+        } else {
+            OpenOptions::new().append(true).open(&self.path)?
+        };
+        file.write_all(data)?;
+        self.received += data.len() as u64;
+        self.total = if whole { Some(self.received) } else { total };
+        if whole || data.is_empty() || self.total.is_some_and(|total| self.received >= total) {
+            self.state = State::Done;
+        }
+        Ok(())
+    }
+
+    /// Percent done, if the size is known
+    pub fn percent(&self) -> Option<u64> {
+        self.total.filter(|total| *total > 0).map(|total| self.received.min(total) * 100 / total)
+    }
+}
+
+enum Error {
+    Refused(String),
+    Failed(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Failed(e.to_string())
+    }
+}
+
+fn failed(e: impl ToString) -> Error {
+    Error::Failed(e.to_string())
+}
+
+fn create(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    File::create(path)
+}
+
+fn header(response: &Response<Body>, name: HeaderName) -> Option<&str> {
+    response.headers().get(name)?.to_str().ok()
+}
+
+// Wait for the server, checking now and then for being told to stop. Ok(None) if told to stop
+async fn patient<T>(future: impl Future<Output = T>, stop: &AtomicBool) -> Result<Option<T>, Error> {
+    let mut future = pin!(future);
+    let start = Instant::now();
+    loop {
+        match tokio::time::timeout(POLL, &mut future).await {
+            Ok(output) => return Ok(Some(output)),
+            Err(_) if stop.load(Ordering::SeqCst) => return Ok(None),
+            Err(_) if start.elapsed() >= STALL => return Err(Error::Failed("Timed out".to_string())),
+            Err(_) => (),
+        }
+    }
+}
+
+// Fetch url into path, from byte `from` on, following redirects. Ok(false) if told to stop
+fn fetch(url: &ServoUrl, path: &Path, from: u64, user_agent: &str, stop: &AtomicBool, progress: impl Fn(u64, Option<u64>)) -> Result<bool, Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let connector = hyper_rustls::HttpsConnectorBuilder::new().with_webpki_roots().https_or_http().enable_http1().build();
+        let client:Client<_, Body> = Client::builder().build(connector);
+
+        let mut url = url.clone();
+        let mut response = 'redirect: {
+            for _ in 0..=REDIRECTS {
+                let mut request = Request::get(url.as_str()).header(header::USER_AGENT, user_agent);
+                if from > 0 {
+                    request = request.header(header::RANGE, format!("bytes={from}-"));
+                }
+                let Some(response) = patient(client.request(request.body(Body::empty()).map_err(failed)?), stop).await? else { return Ok(false) };
+                let response = response.map_err(failed)?;
+                match header(&response, header::LOCATION) {
+                    Some(location) if response.status().is_redirection() => url = url.join(location).map_err(failed)?,
+                    _ => break 'redirect response,
+                }
+            }
+            return Err(Error::Failed("Too many redirects".to_string()));
+        };
+
+        let (mut file, mut received, total) = match response.status() {
+            // "bytes start-end/total"; make sure it starts where asked
+            StatusCode::PARTIAL_CONTENT => {
+                let range = header(&response, header::CONTENT_RANGE).and_then(|range| range.strip_prefix("bytes "));
+                if range.and_then(|range| range.split_once('-')).and_then(|(start, _)| start.parse().ok()) != Some(from) {
+                    return Err(Error::Failed("Server sent the wrong part".to_string()));
+                }
+                let total = range.and_then(|range| range.rsplit_once('/')?.1.parse().ok());
+                (OpenOptions::new().append(true).open(path)?, from, total)
+            },
+            StatusCode::RANGE_NOT_SATISFIABLE if from > 0 => return Ok(true), // There was no more
+            // All of it, even if only the rest was asked for
+            status if status.is_success() => {
+                let total = header(&response, header::CONTENT_LENGTH).and_then(|length| length.parse().ok());
+                (create(path)?, 0, total)
+            },
+            status if status.is_client_error() => return Err(Error::Refused(status.to_string())),
+            status => return Err(Error::Failed(status.to_string())),
+        };
+
+        progress(received, total);
+        let mut told = Instant::now();
+        loop {
+            let Some(data) = patient(response.body_mut().data(), stop).await? else { return Ok(false) };
+            let Some(data) = data else { break };
+            if stop.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+            let data = data.map_err(failed)?;
+            file.write_all(&data)?;
+            received += data.len() as u64;
+            if told.elapsed() >= PROGRESS {
+                progress(received, total);
+                told = Instant::now();
+            }
+        }
+        progress(received, total);
+        Ok(true)
+    })
+}
+
+/// Can servo show a document of this type, or is it better saved?
+pub fn renderable(content_type: &str) -> bool {
+    content_type.is_empty() || content_type.starts_with("text/") || content_type.starts_with("image/")
+        || content_type.contains("html") || content_type.contains("xml")
+}
+
+/// Should following this link save it instead?
+pub fn looks_like(url: &ServoUrl) -> bool {
+    url.path_segments().and_then(|mut segments| segments.next_back())
+        .and_then(|name| name.rsplit_once('.'))
+        .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Where to save url in dir, named after the end of it, that isn't taken yet
+pub fn suggest(dir: &Path, url: &ServoUrl) -> PathBuf {
+    let name = url.path_segments()
+        .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+        .map(|name| name.replace(['/', '\\'], "_").trim_start_matches('.').to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "download".to_string());
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem.to_string(), format!(".{extension}")),
+        _ => (name.clone(), String::new()),
+    };
+    (0..).map(|n| dir.join(if n == 0 { name.clone() } else { format!("{stem}-{n}{extension}") }))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Read the list kept in the profile folder. Anything that was going when cuervo quit comes back failed
+pub fn load(profile: &Path) -> Vec<Download> {
+    let Ok(text) = fs::read_to_string(profile.join(LIST)) else { return vec![] };
+    let Ok(Value::Array(list)) = serde_json::from_str(&text) else { return vec![] };
+    list.iter().enumerate().filter_map(|(id, entry)| {
+        let url = ServoUrl::parse(entry["url"].as_str()?).ok()?;
+        let state = match entry["state"].as_str() {
+            Some("done") => State::Done,
+            Some("cancelled") => State::Cancelled,
+            _ => State::Failed(entry["error"].as_str().unwrap_or("").to_string()),
+        };
+        Some(Download {
+            id, url, path: PathBuf::from(entry["path"].as_str()?), received: entry["received"].as_u64().unwrap_or(0),
+            total: entry["total"].as_u64(), state, tab: None, page: false, fetching: false,
+            attempt: 0, stop: Arc::new(AtomicBool::new(false)),
+        })
+    }).collect()
+}
+
+/// Write the list to the profile folder
+pub fn store(profile: &Path, downloads: &[Download]) -> io::Result<()> {
+    let list:Vec<Value> = downloads.iter().map(|download| {
+        let (state, error) = match &download.state {
+            State::Active => ("active", ""),
+            State::Done => ("done", ""),
+            State::Failed(error) => ("failed", error.as_str()),
+            State::Cancelled => ("cancelled", ""),
+        };
+        json!({
+            "url": download.url.as_str(), "path": download.path.to_string_lossy(), "received": download.received,
+            "total": download.total, "state": state, "error": error,
+        })
+    }).collect();
+    fs::create_dir_all(profile)?;
+    fs::write(profile.join(LIST), Value::Array(list).to_string())
+}
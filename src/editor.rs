@@ -0,0 +1,174 @@
+// Editing a page's text field. Servo says when one takes focus (EmbedderMsg::ShowIME); keys typed here
+// go on to servo as keyboard events, and are mirrored here so there's something to draw.
+
+use ratatui::{
+    crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use servo::compositing::windowing::EmbedderEvent;
+use servo::embedder_traits::InputMethodType;
+use servo::keyboard_types::{Key, KeyState, KeyboardEvent, Modifiers};
+use servo::webrender_api::units::DeviceIntRect;
+use tui_input::backend::crossterm::EventHandler as InputEventHandler;
+use tui_input::Input;
+
+enum Text {
+    Line(Input),
+    Lines(String, usize), // Cursor, in chars
+}
+
+pub struct Editor {
+    text: Text,
+    secret: bool, // Password, so don't show it
+    pub bounds: DeviceIntRect, // The field, in viewport device pixels
+}
+
+impl Editor {
+    /// Servo gives the field's text with the cursor as a byte offset
+    pub fn new(kind: InputMethodType, text: Option<(String, i32)>, multiline: bool, bounds: DeviceIntRect) -> Self {
+        let (value, offset) = text.unwrap_or_default();
+        let cursor = value.get(..offset.max(0) as usize).map_or(value.chars().count(), |before| before.chars().count());
+        let text = if multiline {
+            Text::Lines(value, cursor)
+        } else {
+            Text::Line(Input::new(value).with_cursor(cursor))
+        };
+        Editor { text, secret: kind == InputMethodType::Password, bounds }
+    }
+
+    pub fn multiline(&self) -> bool {
+        matches!(self.text, Text::Lines(..))
+    }
+
+    /// The field's text, as far as we've seen it typed
+    pub fn value(&self) -> String {
+        match &self.text {
+            Text::Line(input) => input.value().to_string(),
+            Text::Lines(text, _) => text.clone(),
+        }
+    }
+
+    /// Replace the field's text by deleting what's there and typing the new text. Returns what to send servo
+    pub fn replace(&mut self, value: &str) -> Vec<EmbedderEvent> {
+        let (before, after) = match &self.text {
+            Text::Line(input) => (input.cursor(), input.value().chars().count() - input.cursor()),
+            Text::Lines(text, cursor) => (*cursor, text.chars().count() - cursor),
+        };
+        let press = |code| keyboard(KeyEvent::new(code, KeyModifiers::NONE));
+        let mut events:Vec<EmbedderEvent> = (0..before).flat_map(|_| press(KeyCode::Backspace))
+            .chain((0..after).flat_map(|_| press(KeyCode::Delete)))
+            .collect();
+
+        let value = if self.multiline() { value.to_string() } else { value.replace('\n', " ") };
+        events.extend(value.chars().flat_map(|c| press(if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) })));
+        let count = value.chars().count();
+        self.text = match self.text {
+            Text::Line(_) => Text::Line(Input::new(value)),
+            Text::Lines(..) => Text::Lines(value, count),
+        };
+        events
+    }
+
+    /// Take a key press. Returns what to send servo
+    pub fn key(&mut self, key: KeyEvent) -> Vec<EmbedderEvent> {
+        // Ctrl and Alt keys mean something to the page, not the text
+        if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            match &mut self.text {
+                Text::Line(input) => { input.handle_event(&Event::Key(key)); },
+                Text::Lines(text, cursor) => edit(text, cursor, key.code),
+            }
+        }
+        keyboard(key)
+    }
+
+    /// Draw over area, and put the terminal cursor where the text cursor is
+    pub fn render(&self, area: Rect, f: &mut Frame) {
+        let hide = |text: &str| if self.secret { "•".repeat(text.chars().count()) } else { text.to_string() };
+        let (lines, (line, col)) = match &self.text {
+            Text::Line(input) => (vec![hide(input.value())], (0, input.visual_cursor())),
+            Text::Lines(text, cursor) => (text.split('\n').map(hide).collect(), line_col(text, *cursor)),
+        };
+        // Keep the cursor in view
+        let scroll_y = line.saturating_sub(area.height.max(1) as usize - 1);
+        let scroll_x = col.saturating_sub(area.width.max(1) as usize - 1);
+        let lines:Vec<Line> = lines.into_iter().map(Line::from).collect();
+
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).reversed().scroll((scroll_y as u16, scroll_x as u16)), area);
+        f.set_cursor_position((area.x + (col - scroll_x) as u16, area.y + (line - scroll_y) as u16));
+    }
+}
+
+// Line and column of a cursor, counted in chars
+fn line_col(text: &str, cursor: usize) -> (usize, usize) {
+    let before:String = text.chars().take(cursor).collect();
+    let line = before.matches('\n').count();
+    (line, before.chars().count() - before.rfind('\n').map_or(0, |i| before[..=i].chars().count()))
+}
+
+// Cursor for a line and column, the column kept within the line
+fn cursor_at(text: &str, line: usize, col: usize) -> usize {
+    let mut cursor = 0;
+    for (i, this) in text.split('\n').enumerate() {
+        if i == line {
+            return cursor + col.min(this.chars().count());
+        }
+        cursor += this.chars().count() + 1;
+    }
+    text.chars().count()
+}
+
+fn byte(text: &str, cursor: usize) -> usize {
+    text.char_indices().nth(cursor).map_or(text.len(), |(i, _)| i)
+}
+
+// Multi-line editing, as a plain textarea would do it
+fn edit(text: &mut String, cursor: &mut usize, code: KeyCode) {
+    let (line, col) = line_col(text, *cursor);
+    let count = text.chars().count();
+    match code {
+        KeyCode::Char(c) => { text.insert(byte(text, *cursor), c); *cursor += 1; },
+        KeyCode::Enter => { text.insert(byte(text, *cursor), '\n'); *cursor += 1; },
+        KeyCode::Backspace if *cursor > 0 => { *cursor -= 1; text.remove(byte(text, *cursor)); },
+        KeyCode::Delete if *cursor < count => { text.remove(byte(text, *cursor)); },
+        KeyCode::Left => *cursor = cursor.saturating_sub(1),
+        KeyCode::Right => *cursor = (*cursor + 1).min(count),
+        KeyCode::Up if line > 0 => *cursor = cursor_at(text, line - 1, col),
+        KeyCode::Down => *cursor = cursor_at(text, line + 1, col),
+        KeyCode::Home => *cursor = cursor_at(text, line, 0),
+        KeyCode::End => *cursor = cursor_at(text, line, usize::MAX),
+        _ => ()
+    }
+}
+
+// Key press as servo wants it, down then up
+fn keyboard(key: KeyEvent) -> Vec<EmbedderEvent> {
+    let named = match key.code {
+        KeyCode::Char(c) => Key::Character(c.to_string()),
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Left => Key::ArrowLeft,
+        KeyCode::Right => Key::ArrowRight,
+        KeyCode::Up => Key::ArrowUp,
+        KeyCode::Down => Key::ArrowDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Tab | KeyCode::BackTab => Key::Tab,
+        KeyCode::Esc => Key::Escape,
+        _ => return vec![]
+    };
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::SHIFT, key.modifiers.contains(KeyModifiers::SHIFT) || key.code == KeyCode::BackTab);
+    modifiers.set(Modifiers::CONTROL, key.modifiers.contains(KeyModifiers::CONTROL));
+    modifiers.set(Modifiers::ALT, key.modifiers.contains(KeyModifiers::ALT));
+    let down = KeyboardEvent { state: KeyState::Down, key: named, modifiers, ..Default::default() };
+    let up = KeyboardEvent { state: KeyState::Up, ..down.clone() };
+    vec![EmbedderEvent::Keyboard(down), EmbedderEvent::Keyboard(up)]
+}
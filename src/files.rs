@@ -0,0 +1,184 @@
+// File picker, for a page's <input type=file>. Starts in the uploads folder from config;
+// handing the page files from outside it takes a yes first.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::KeyCode,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+use servo::embedder_traits::FilterPattern;
+use servo::ipc_channel::ipc::IpcSender;
+
+struct Entry {
+    name: String,
+    dir: bool,
+}
+
+pub struct Picker {
+    root: PathBuf,
+    dir: PathBuf, // Folder shown
+    all: Vec<Entry>, // Everything in dir, folders first
+    shown: Vec<usize>, // Indexes into all that match the filter and the page's types
+    selected: usize, // Index into shown
+    filter: String, // Typed, to narrow the list
+    extensions: Vec<String>, // What the page accepts, lower case. Empty for anything
+    multiple: bool,
+    marked: Vec<PathBuf>, // Chosen so far, if multiple
+    error: Option<String>, // Couldn't list dir
+    pub outside: Option<Vec<PathBuf>>, // Chosen, but some are outside root, so waiting for a yes
+    reply: IpcSender<Option<Vec<String>>>,
+}
+
+impl Picker {
+    pub fn new(root: &Path, filters: Vec<FilterPattern>, multiple: bool, reply: IpcSender<Option<Vec<String>>>) -> Self {
+        let _ = fs::create_dir_all(root); // If this fails, read() says why
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let extensions = filters.into_iter().map(|FilterPattern(pattern)| pattern.trim_start_matches('.').to_lowercase()).collect();
+        let mut picker = Picker {
+            dir: root.clone(), root, all: vec![], shown: vec![], selected: 0, filter: String::new(), extensions,
+            multiple, marked: vec![], error: None, outside: None, reply,
+        };
+        picker.read();
+        picker
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn multiple(&self) -> bool {
+        self.multiple
+    }
+
+    fn read(&mut self) {
+        self.all.clear();
+        self.error = None;
+        match fs::read_dir(&self.dir) {
+            Ok(entries) => for entry in entries.flatten() {
+                let dir = entry.path().is_dir(); // Following links
+                self.all.push(Entry { name: entry.file_name().to_string_lossy().into_owned(), dir });
+            },
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.all.sort_by(|a, b| b.dir.cmp(&a.dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+        self.refilter();
+    }
+
+    fn refilter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.shown = self.all.iter().enumerate().filter(|(_, entry)| {
+            let name = entry.name.to_lowercase();
+            // Hidden files only when asked for
+            (!name.starts_with('.') || filter.starts_with('.'))
+                && name.contains(&filter)
+                && (entry.dir || self.extensions.is_empty()
+                    || name.rsplit_once('.').is_some_and(|(_, extension)| self.extensions.iter().any(|e| e == extension)))
+        }).map(|(i, _)| i).collect();
+        self.selected = self.selected.min(self.shown.len().saturating_sub(1));
+    }
+
+    fn enter(&mut self, dir: PathBuf) {
+        self.dir = dir.canonicalize().unwrap_or(dir);
+        self.filter.clear();
+        self.selected = 0;
+        self.read();
+    }
+
+    fn current(&self) -> Option<&Entry> {
+        self.all.get(*self.shown.get(self.selected)?)
+    }
+
+    /// Take a key press. True once the page has its answer
+    pub fn key(&mut self, code: KeyCode) -> bool {
+        if let Some(paths) = &self.outside {
+            match code {
+                KeyCode::Char('y') => return self.answer(Some(paths.clone())),
+                KeyCode::Char('n') | KeyCode::Esc => self.outside = None,
+                _ => ()
+            }
+            return false;
+        }
+        match code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(self.shown.len().saturating_sub(1)),
+            KeyCode::Left => if let Some(parent) = self.dir.parent() { self.enter(parent.to_path_buf()) },
+            KeyCode::Backspace if self.filter.is_empty() => if let Some(parent) = self.dir.parent() { self.enter(parent.to_path_buf()) },
+            KeyCode::Backspace => { self.filter.pop(); self.refilter(); },
+            KeyCode::Char(c) => { self.filter.push(c); self.refilter(); },
+            KeyCode::Tab if self.multiple => if let Some(entry) = self.current().filter(|entry| !entry.dir) {
+                let path = self.dir.join(&entry.name);
+                match self.marked.iter().position(|marked| *marked == path) {
+                    Some(i) => { self.marked.remove(i); },
+                    None => self.marked.push(path),
+                }
+            },
+            KeyCode::Right | KeyCode::Enter => match self.current() {
+                Some(entry) if entry.dir => self.enter(self.dir.join(&entry.name)),
+                Some(entry) if code == KeyCode::Enter => {
+                    let paths = if self.marked.is_empty() { vec![self.dir.join(&entry.name)] } else { self.marked.clone() };
+                    return self.choose(paths);
+                },
+                None if code == KeyCode::Enter && !self.marked.is_empty() => return self.choose(self.marked.clone()),
+                _ => ()
+            },
+            KeyCode::Esc => return self.answer(None),
+            _ => ()
+        }
+        false
+    }
+
+    fn choose(&mut self, paths: Vec<PathBuf>) -> bool {
+        let inside = |path: &PathBuf| path.canonicalize().is_ok_and(|path| path.starts_with(&self.root));
+        if paths.iter().all(inside) {
+            self.answer(Some(paths))
+        } else {
+            self.outside = Some(paths);
+            false
+        }
+    }
+
+    fn answer(&self, paths: Option<Vec<PathBuf>>) -> bool {
+        let _ = self.reply.send(paths.map(|paths| paths.iter().map(|path| path.to_string_lossy().into_owned()).collect()));
+        true
+    }
+
+    /// Draw over area: the filter, the listing, then `footer` (which keys do what, or the question about outside files)
+    pub fn render(&self, area: Rect, buf: &mut Buffer, footer: &str) {
+        let block = Block::bordered().title(self.dir.display().to_string());
+        let inner = block.inner(area);
+        Clear.render(area, buf);
+        block.render(area, buf);
+        let [filter_area, list_area, footer_area] = Layout::vertical([
+            Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)
+        ]).areas(inner);
+
+        Line::from(format!("> {}", self.filter)).render(filter_area, buf);
+        if let Some(error) = &self.error {
+            Paragraph::new(error.as_str()).red().render(list_area, buf);
+        } else {
+            let rows = list_area.height as usize;
+            let scroll = (self.selected + 1).saturating_sub(rows);
+            let lines:Vec<Line> = self.shown.iter().enumerate().skip(scroll).take(rows).map(|(i, &entry)| {
+                let entry = &self.all[entry];
+                let mark = match (self.multiple, entry.dir) {
+                    (true, false) if self.marked.contains(&self.dir.join(&entry.name)) => "[x] ",
+                    (true, false) => "[ ] ",
+                    (true, true) => "    ",
+                    (false, _) => "",
+                };
+                let slash = if entry.dir { "/" } else { "" };
+                let style = if i == self.selected { Style::new().reversed() } else { Style::new() };
+                Line::styled(format!("{mark}{}{slash}", entry.name), style)
+            }).collect();
+            Paragraph::new(lines).render(list_area, buf);
+        }
+        let footer = Paragraph::new(footer);
+        if self.outside.is_some() { footer.yellow() } else { footer.dark_gray() }.render(footer_area, buf);
+    }
+}
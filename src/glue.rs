@@ -17,16 +17,9 @@ use servo::compositing::windowing::{
 
 /// Callbacks. Implemented by embedder. Called by Servo.
 pub trait HostTrait {
-    /// Show alert.
-    // fn prompt_alert(&self, msg: String, trusted: bool);
-    // /// Ask Yes/No question.
-    // fn prompt_yes_no(&self, msg: String, trusted: bool) -> PromptResult;
-    // /// Ask Ok/Cancel question.
-    // fn prompt_ok_cancel(&self, msg: String, trusted: bool) -> PromptResult;
-    // /// Ask for string
-    // fn prompt_input(&self, msg: String, default: String, trusted: bool) -> Option<String>;
-    // /// Show context menu
-    // fn show_context_menu(&self, title: Option<String>, items: Vec<String>);
+    // Alerts, yes/no, ok/cancel and input prompts need an answer from the user, which has to wait for
+    // the event loop, so main.rs shows them as UiState::Dialog and answers EmbedderMsg::Prompt directly.
+    // Context menus are answered the same way, as UiState::Menu for EmbedderMsg::ShowContextMenu.
     // /// Page starts loading.
     // /// "Reload button" should be disabled.
     // /// "Stop button" should be enabled.
@@ -64,20 +57,8 @@ pub trait HostTrait {
     fn on_animating_changed(&self, animating: bool);
     // /// Servo finished shutting down.
     // fn on_shutdown_complete(&self);
-    // /// A text input is focused.
-    // fn on_ime_show(
-    //     &self,
-    //     input_type: InputMethodType,
-    //     text: Option<(String, i32)>,
-    //     multiline: bool,
-    //     bounds: DeviceIntRect,
-    // );
-    // /// Input lost focus
-    // fn on_ime_hide(&self);
-    // /// Gets sytem clipboard contents.
-    // fn get_clipboard_contents(&self) -> Option<String>;
-    // /// Sets system clipboard contents.
-    // fn set_clipboard_contents(&self, contents: String);
+    // Text fields taking and losing focus come as EmbedderMsg::ShowIME/HideIME, and open UiState::Edit.
+    // Clipboard requests are answered from App::clipboard, for EmbedderMsg::Get/SetClipboardContents.
     // /// Called when we get the media session metadata/
     // fn on_media_session_metadata(&self, title: String, artist: String, album: String);
     // /// Called when the media session playback state changes.
@@ -135,6 +116,16 @@ impl WindowCallbacks {
             rendering_context,
         }
     }
+
+    pub fn coordinates(&self) -> Coordinates {
+        self.coordinates.borrow().clone()
+    }
+
+    /// Take new coordinates and resize the surface to match
+    pub fn resize(&self, coordinates: Coordinates) {
+        let _ = self.rendering_context.resize(coordinates.framebuffer.to_untyped());
+        *self.coordinates.borrow_mut() = coordinates;
+    }
 }
 
 impl WindowMethods for WindowCallbacks {
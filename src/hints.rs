@@ -0,0 +1,83 @@
+// Link hints: label every link on screen, type a label to follow it.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+};
+
+use crate::page::{ElementKind, Lines, Page};
+
+const HINT_CHARS:&[char] = &['a', 's', 'd', 'f', 'g', 'h', 'j', 'k', 'l'];
+const HINT_STYLE:Style = Style::new().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD);
+
+struct Target {
+    label: String,
+    element: usize,
+    row: usize, // In lines
+    col: u16,
+}
+
+pub struct Hints {
+    typed: String,
+    targets: Vec<Target>,
+}
+
+impl Hints {
+    /// Label links visible with `height` rows shown from `scroll`. None if there aren't any.
+    pub fn new(page: &Page, laid: &Lines, scroll: usize, height: u16) -> Option<Self> {
+        let mut seen = std::collections::HashSet::new();
+        let visible:Vec<_> = laid.hits.iter()
+            .filter(|hit| hit.row >= scroll && hit.row < scroll + height as usize)
+            .filter(|hit| page.elements.get(hit.element)
+                .is_some_and(|e| e.kind == ElementKind::Link && e.href.is_some()))
+            .filter(|hit| seen.insert(hit.element)) // First appearance of each link only
+            .collect();
+        if visible.is_empty() {
+            return None;
+        }
+
+        // All labels the same length, so none is a prefix of another
+        let mut length = 1;
+        while HINT_CHARS.len().pow(length) < visible.len() {
+            length += 1;
+        }
+        let targets = visible.iter().enumerate().map(|(i, hit)| {
+            let mut label = String::new();
+            let mut n = i;
+            for _ in 0..length {
+                label.insert(0, HINT_CHARS[n % HINT_CHARS.len()]);
+                n /= HINT_CHARS.len();
+            }
+            Target { label, element: hit.element, row: hit.row, col: hit.col }
+        }).collect();
+
+        Some(Hints { typed: String::new(), targets })
+    }
+
+    /// Take a typed character. Returns the chosen element once a label is complete.
+    pub fn push(&mut self, c: char) -> Option<usize> {
+        self.typed.push(c);
+        if !self.targets.iter().any(|t| t.label.starts_with(&self.typed)) {
+            self.typed.pop(); // Not a label, ignore
+        }
+        self.targets.iter().find(|t| t.label == self.typed).map(|t| t.element)
+    }
+
+    pub fn pop(&mut self) {
+        self.typed.pop();
+    }
+
+    /// Draw labels over the page, as drawn from `scroll` into `area`
+    pub fn render(&self, scroll: usize, area: Rect, buf: &mut Buffer) {
+        for target in self.targets.iter().filter(|t| t.label.starts_with(&self.typed)) {
+            let Some(row) = target.row.checked_sub(scroll) else { continue };
+            let (x, y) = (area.x + target.col, area.y + row as u16);
+            if y >= area.bottom() || x >= area.right() {
+                continue;
+            }
+            let rest = &target.label[self.typed.len()..];
+            buf.set_stringn(x, y, rest, (area.right() - x) as usize, HINT_STYLE);
+        }
+    }
+}
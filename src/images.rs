@@ -0,0 +1,201 @@
+// Inline images, for terminals that can show them (Kitty, iTerm2 or Sixel).
+// Image contents come from the page by way of scripts/image.js, and are written straight
+// to the terminal after ratatui draws, into cells ratatui was told to skip.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use ratatui::{buffer::Buffer, crossterm::{cursor::MoveTo, queue}};
+
+use crate::tty;
+
+const KITTY_CHUNK:usize = 4096; // Kitty wants base64 payloads split this small
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol { Kitty, Iterm2, Sixel }
+
+/// Where an image goes on screen, in cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub element: usize,
+    pub x: u16,
+    pub y: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+pub struct Images {
+    pub protocol: Option<Protocol>,
+    pub cell: (u16, u16), // Size of a terminal cell in screen pixels
+    requested: HashSet<usize>,
+    ready: HashMap<usize, String>, // Kitty/iTerm2: base64 PNG. Sixel: whole escape sequence
+    sizes: HashMap<usize, (u32, u32)>, // Pixel size asked for
+    shown: Vec<Placement>,
+}
+
+/// What the terminal can do: config says (kitty, iterm, sixel or none), else what the terminal answered, else a guess
+pub fn detect(forced: Option<&str>, answers: &tty::Answers) -> Option<Protocol> {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    match forced.unwrap_or_default() {
+        "kitty" => return Some(Protocol::Kitty),
+        "iterm" => return Some(Protocol::Iterm2),
+        "sixel" => return Some(Protocol::Sixel),
+        "none" => return None,
+        _ => ()
+    }
+    // The environment is still asked, for iTerm2 (which has no query) and for replies tmux didn't pass on
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+    if answers.kitty || term == "xterm-kitty" || !var("KITTY_WINDOW_ID").is_empty() || program == "ghostty" {
+        Some(Protocol::Kitty)
+    } else if program == "iTerm.app" || program == "WezTerm" || var("LC_TERMINAL") == "iTerm2" {
+        Some(Protocol::Iterm2)
+    } else if answers.sixel || term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") || program == "mintty" {
+        Some(Protocol::Sixel)
+    } else {
+        None
+    }
+}
+
+impl Images {
+    pub fn new(protocol: Option<Protocol>, cell: (u16, u16)) -> Self {
+        Images { protocol, cell, requested: Default::default(), ready: Default::default(), sizes: Default::default(), shown: vec![] }
+    }
+
+    /// Forget everything about the previous page
+    pub fn clear(&mut self) {
+        self.requested.clear();
+        self.ready.clear();
+        self.sizes.clear();
+    }
+
+    /// The screen was wiped (by running another program), so draw images again next time
+    pub fn redraw(&mut self) {
+        self.shown.clear();
+    }
+
+    /// If this image hasn't been asked for yet, mark it asked for and give arguments for image.js
+    pub fn request(&mut self, placement: &Placement) -> Option<Vec<serde_json::Value>> {
+        let protocol = self.protocol?;
+        if !self.requested.insert(placement.element) {
+            return None;
+        }
+        let size = (placement.cols as u32 * self.cell.0 as u32, placement.rows as u32 * self.cell.1 as u32);
+        self.sizes.insert(placement.element, size);
+        let format = if protocol == Protocol::Sixel { "rgba" } else { "png" };
+        Some(vec![placement.element.into(), size.0.into(), size.1.into(), format.into()])
+    }
+
+    /// Take the result of image.js
+    pub fn receive(&mut self, element: usize, data: String) {
+        let ready = match self.protocol {
+            Some(Protocol::Sixel) => {
+                let (Some(&(width, height)), Ok(rgba)) = (self.sizes.get(&element), BASE64.decode(data)) else { return };
+                if rgba.len() < (width * height * 4) as usize { return }
+                sixel(&rgba, width as usize, height as usize)
+            },
+            Some(_) => data,
+            None => return
+        };
+        self.ready.insert(element, ready);
+    }
+
+    /// Keep ratatui from drawing over images we can show, and say which those are
+    pub fn prepare(&self, placements: Vec<Placement>, buf: &mut Buffer) -> Vec<Placement> {
+        let area = buf.area;
+        placements.into_iter().filter(|p| self.ready.contains_key(&p.element)).inspect(|p| {
+            for y in p.y..(p.y + p.rows).min(area.bottom()) {
+                for x in p.x..(p.x + p.cols).min(area.right()) {
+                    buf[(x, y)].set_skip(true);
+                }
+            }
+        }).collect()
+    }
+
+    /// Call after drawing with whatever prepare() returned. Only writes if something moved.
+    pub fn show(&mut self, placements: Vec<Placement>) -> io::Result<()> {
+        if placements == self.shown {
+            return Ok(());
+        }
+        let mut out = io::stdout().lock();
+        if self.protocol == Some(Protocol::Kitty) {
+            write!(out, "\x1b_Ga=d,q=2\x1b\\")?; // Kitty images stay until deleted
+        }
+        for p in &placements {
+            let Some(data) = self.ready.get(&p.element) else { continue };
+            queue!(out, MoveTo(p.x, p.y))?;
+            match self.protocol {
+                Some(Protocol::Kitty) => {
+                    let chunks:Vec<&[u8]> = data.as_bytes().chunks(KITTY_CHUNK).collect();
+                    for (i, chunk) in chunks.iter().enumerate() {
+                        let more = (i + 1 < chunks.len()) as u8;
+                        if i == 0 {
+                            write!(out, "\x1b_Ga=T,f=100,q=2,C=1,c={},r={},m={more};", p.cols, p.rows)?;
+                        } else {
+                            write!(out, "\x1b_Gm={more};")?;
+                        }
+                        out.write_all(chunk)?;
+                        write!(out, "\x1b\\")?;
+                    }
+                },
+                Some(Protocol::Iterm2) =>
+                    write!(out, "\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=0:{data}\x07", p.cols, p.rows)?,
+                Some(Protocol::Sixel) => out.write_all(data.as_bytes())?,
+                None => ()
+            }
+        }
+        out.flush()?;
+        self.shown = placements;
+        Ok(())
+    }
+}
+
+// Encode RGBA as sixel, against a fixed 6x6x6 color cube
+fn sixel(rgba: &[u8], width: usize, height: usize) -> String {
+    let level = |c: u8| (c as usize * 5 + 127) / 255;
+    let colors:Vec<Option<usize>> = rgba.chunks_exact(4).take(width * height)
+        .map(|p| (p[3] >= 128).then(|| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])))
+        .collect();
+
+    let mut out = format!("\x1bPq\"1;1;{width};{height}");
+    for i in 0..216 {
+        out += &format!("#{i};2;{};{};{}", i / 36 * 20, i / 6 % 6 * 20, i % 6 * 20);
+    }
+    for top in (0..height).step_by(6) {
+        let rows = (height - top).min(6);
+        let mut used = [false; 216];
+        for y in top..top + rows {
+            for color in colors[y * width..(y + 1) * width].iter().flatten() {
+                used[*color] = true;
+            }
+        }
+        for color in (0..216).filter(|c| used[*c]) {
+            out += &format!("#{color}");
+            // Run length encode columns of this color
+            let mut run:Option<(char, usize)> = None;
+            for x in 0..=width {
+                let sixel = (x < width).then(|| {
+                    let bits = (0..rows).filter(|dy| colors[(top + dy) * width + x] == Some(color))
+                        .fold(0, |bits, dy| bits | 1 << dy);
+                    (63 + bits) as u8 as char
+                });
+                match (run, sixel) {
+                    (Some((c, n)), Some(s)) if c == s => run = Some((c, n + 1)),
+                    _ => {
+                        match run {
+                            Some((c, n)) if n > 3 => out += &format!("!{n}{c}"),
+                            Some((c, n)) => out.extend(std::iter::repeat(c).take(n)),
+                            None => ()
+                        }
+                        run = sixel.map(|s| (s, 1));
+                    }
+                }
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out += "\x1b\\";
+    out
+}
@@ -1,21 +1,42 @@
 // Text based web browser (experimental)
 // Based on Ratatui popup example
 
+mod address;
+mod clipboard;
+mod config;
+mod download;
+mod editor;
+mod files;
 mod glue;
-
-use std::{error::Error, io};
-use std::cell::RefCell;
+mod hints;
+mod images;
+mod menu;
+mod page;
+mod pixels;
+mod tab;
+mod tty;
+mod webdriver;
+
+use std::{error::Error, fs, io};
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::process::Command;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
 
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind},
         execute,
-        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+        terminal::{self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
     layout::{Constraint, Layout, Rect},
-    style::Stylize,
+    style::{Style, Stylize},
+    text::{Line, Span},
     widgets::{Block, Clear, Paragraph, Wrap},
     Frame, Terminal,
 };
@@ -23,21 +44,75 @@ use ratatui::{
 use fluent::{FluentBundle, FluentValue, FluentResource, FluentArgs, FluentError};
 use unic_langid::LanguageIdentifier;
 
-use servo::embedder_traits::{EventLoopWaker, EmbedderMsg, EmbedderProxy};
-use servo::compositing::windowing::{EmbedderEvent, EmbedderMethods};
+use servo::embedder_traits::{ContextMenuResult, EventLoopWaker, EmbedderMsg, EmbedderProxy, PromptDefinition, PromptOrigin, PromptResult};
+use servo::ipc_channel::ipc::IpcSender;
+use servo::compositing::windowing::{EmbedderEvent, EmbedderMethods, MouseWindowEvent};
+use servo::script_traits::{MouseButton, TouchEventType, TraversalDirection, WheelDelta, WheelMode};
+use servo::webrender_api::ScrollLocation;
+use servo::webrender_api::units::{DevicePoint, LayoutVector2D};
 use servo::servo_url::ServoUrl;
+use servo::TopLevelBrowsingContextId;
 use servo::webrender_traits::RenderingContext;
 use servo_net::protocols::ProtocolRegistry;
 use surfman::{Connection, Context, Device, SurfaceType};
 
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use tui_input::backend::crossterm::EventHandler as InputEventHandler;
 use tui_input::Input;
 
 const VERSION:&str = "cuervo 0.1b"; // Not localized
 
-enum UiState { Base, Goto(Input) }
+const WHEEL_LINES:isize = 3; // Rows scrolled per mouse wheel notch
+const TAB_ROWS:u16 = 1; // Tab strip
+const STATUS_ROWS:u16 = 1; // Status bar
+const CHROME_ROWS:u16 = TAB_ROWS + STATUS_ROWS;
+const TAB_WIDTH:usize = 20; // Most characters of a tab's title shown in the strip
+const FIELD_WIDTH:u16 = 20; // Least size of a text field's editor
+const FIELD_ROWS:u16 = 5; // Same, for textareas
+
+const SNAPSHOT_JS:&str = include_str!("scripts/snapshot.js");
+const IMAGE_JS:&str = include_str!("scripts/image.js");
+const ACTIVATE_JS:&str = include_str!("scripts/activate.js");
+const FETCH_JS:&str = include_str!("scripts/fetch.js");
+const SELECTION_JS:&str = include_str!("scripts/selection.js");
+const FIELD_JS:&str = include_str!("scripts/field.js");
+const CHOOSE_JS:&str = include_str!("scripts/choose.js");
+
+const INPUT_POLL:Duration = Duration::from_millis(50); // How often the input thread checks whether to pause
+
+enum UiState {
+    Base,
+    Goto(Input),
+    Hint(hints::Hints, bool), // True to open in a new tab
+    Popup(IpcSender<bool>, Option<TopLevelBrowsingContextId>), // Asking whether this tab may open a new one
+    Dialog(PromptDefinition, bool, Input), // alert(), confirm() or prompt(); true if from the page; text typed for prompt()
+    Menu(menu::Menu),
+    Edit(editor::Editor), // Typing into a page's text field
+    Files(files::Picker), // Choosing files for the page to upload
+    SaveAs(Input, ServoUrl, TopLevelBrowsingContextId), // Asking where to save a download, and which tab fetches it
+    Downloads(usize), // Downloads panel, and which one is selected
+}
+
+// Answer a dialog without asking
+fn dismiss(definition: PromptDefinition) {
+    let _ = match definition {
+        PromptDefinition::Alert(_, sender) => sender.send(()),
+        PromptDefinition::YesNo(_, sender) | PromptDefinition::OkCancel(_, sender) => sender.send(PromptResult::Dismissed),
+        PromptDefinition::Input(_, _, sender) => sender.send(None),
+    };
+}
 
-enum BarState { None, UrlParse(String), UrlLoading }
+enum BarState { None, UrlParse(String), UrlLoading(Instant), Notice(String, Instant) } // Loading since when; already localized message, shown until when
+
+impl BarState {
+    fn notice(text: String) -> Self {
+        BarState::Notice(text, Instant::now() + NOTICE_TIME)
+    }
+}
+
+const SPINNER:&[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_INTERVAL:Duration = Duration::from_millis(100);
+const NOTICE_TIME:Duration = Duration::from_secs(5); // Then the title and URL are back
 
 #[cfg(feature = "debug_mode")]
 const DEBUG_DISPLAY_FRESH:std::time::Duration = std::time::Duration::from_millis(100);
@@ -54,44 +129,694 @@ struct DebugMode {
 
 struct App {
     state: UiState,
-    bar_state: BarState,
     strings: FluentBundle<FluentResource>,
-    browser_id: servo::TopLevelBrowsingContextId,
+    tabs: Vec<tab::Tab>, // Never empty
+    current: usize, // Tab shown
     servo: servo::Servo<glue::WindowCallbacks>,
+    window: Rc<glue::WindowCallbacks>,
+    scripts: webdriver::Scripts,
+    view_height: u16, // Rows available to show the page
+    pending: Option<char>, // First key of a two key command
+    field: Option<editor::Editor>, // Text field the page focused by itself, until the user asks to edit it
+    activated: bool, // User clicked something since the page loaded, so a text field taking focus is their doing
+    opener: Option<TopLevelBrowsingContextId>, // Tab last allowed to open a popup, until the popup appears
+    pixels: pixels::Pixels, // Last composited frame
+    graphical: bool, // Show pixels instead of text
+    images: images::Images,
+    downloads: Vec<download::Download>,
+    user_agent: String, // Downloads send it too
+    wakes: Sender<Wake>, // For download threads
+    clipboard: clipboard::Clipboard,
+    density: f32, // Device pixels per CSS pixel
+    config: config::Config,
+    animating: Rc<Cell<bool>>, // Set by HostHandler
+    input: InputThread,
+    last_frame: Instant,
     #[cfg(feature = "debug_mode")]
     debug_display: Option<DebugMode>, // If non-None do debug
 }
 
 impl App {
-    const fn new(strings: FluentBundle<FluentResource>, browser_id: servo::TopLevelBrowsingContextId, servo: servo::Servo<glue::WindowCallbacks>) -> Self {
-        Self {
-            state: UiState::Base, bar_state:BarState::None, strings, browser_id, servo,
+    fn tab(&self) -> &tab::Tab {
+        &self.tabs[self.current]
+    }
 
-            #[cfg(feature = "debug_mode")]
-            debug_display:None
+    fn tab_mut(&mut self) -> &mut tab::Tab {
+        &mut self.tabs[self.current]
+    }
+
+    // Scripts only run in the tab shown
+    fn run_script(&self, purpose: webdriver::Purpose, script: &'static str, args: Vec<serde_json::Value>) {
+        self.scripts.run(self.tab().id, purpose, script, args);
+    }
+
+    // Lay out page again if it or the width changed, and ask for any images it now has room for
+    fn relayout(&mut self, width: u16, height: u16) {
+        self.view_height = height.saturating_sub(CHROME_ROWS);
+        let tab = &mut self.tabs[self.current];
+        let Some(page) = &tab.page else { return };
+        if tab.laid.as_ref().is_some_and(|laid| laid.width == width) {
+            return;
+        }
+        // Images are sized by how many CSS pixels fit in a cell on screen
+        let cell = self.images.protocol.map(|_| (self.images.cell.0 as f32, self.images.cell.1 as f32));
+        let laid = page.layout(width, cell);
+        for (_, picture) in &laid.pictures {
+            if let Some(args) = self.images.request(picture) {
+                self.scripts.run(tab.id, webdriver::Purpose::Image(picture.element), IMAGE_JS, args);
+            }
+        }
+        tab.laid = Some(laid);
+        let scroll = self.tab().scroll.min(self.max_scroll());
+        self.tab_mut().scroll = scroll;
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.tab().laid.as_ref().map_or(0, |laid| laid.lines.len().saturating_sub(self.view_height as usize))
+    }
+
+    // Scroll text view and servo together. Servo may be able to go further than the text
+    fn scroll_by(&mut self, rows: isize) {
+        let scroll = self.tab().scroll.saturating_add_signed(rows).min(self.max_scroll());
+        self.tab_mut().scroll = scroll;
+        // Two device pixels per row; positive deltas scroll up
+        self.servo_scroll(ScrollLocation::Delta(LayoutVector2D::new(0.0, -rows as f32 * 2.0)));
+    }
+
+    fn scroll_home(&mut self, end: bool) {
+        let scroll = if end { self.max_scroll() } else { 0 };
+        self.tab_mut().scroll = scroll;
+        self.servo_scroll(if end { ScrollLocation::End } else { ScrollLocation::Start });
+    }
+
+    fn servo_scroll(&mut self, location: ScrollLocation) {
+        let center = self.window.coordinates().viewport.center();
+        self.servo.handle_events(vec![EmbedderEvent::Scroll(location, center, TouchEventType::Move)]);
+    }
+
+    // How far down the page we are, for the status bar. Text view only: servo doesn't say where it's scrolled to
+    fn scroll_percent(&self) -> Option<usize> {
+        if self.graphical {
+            return None;
+        }
+        let total = self.tab().laid.as_ref()?.lines.len();
+        (total > 0).then(|| (self.tab().scroll + self.view_height as usize).min(total) * 100 / total)
+    }
+
+    // Move focus cursor to the next/previous element that can take it and is on the page
+    fn move_focus(&mut self, forward: bool) {
+        let tab = self.tab();
+        let (Some(page), Some(laid)) = (&tab.page, &tab.laid) else { return };
+        let order:Vec<usize> = page.elements.iter().enumerate()
+            .filter(|(i, element)| element.focusable() && laid.row_of(*i).is_some())
+            .map(|(i, _)| i)
+            .collect();
+        if order.is_empty() {
+            return;
+        }
+        let next = match tab.focus.and_then(|focus| order.iter().position(|&i| i == focus)) {
+            Some(at) if forward => (at + 1) % order.len(),
+            Some(at) => (at + order.len() - 1) % order.len(),
+            None if forward => 0,
+            None => order.len() - 1,
+        };
+        let (row, scroll) = (laid.row_of(order[next]), tab.scroll);
+        self.tab_mut().focus = Some(order[next]);
+
+        // Bring it on screen
+        if let Some(row) = row {
+            let height = self.view_height.max(1) as usize;
+            if row < scroll {
+                self.scroll_by(row as isize - scroll as isize);
+            } else if row >= scroll + height {
+                self.scroll_by((row + 1 - height) as isize - scroll as isize);
+            }
+        }
+    }
+
+    // Full click (down, up, click) at a point in the servo viewport
+    fn click(&mut self, point: DevicePoint) {
+        self.servo.handle_events(vec![
+            EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::MouseDown(MouseButton::Left, point)),
+            EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::MouseUp(MouseButton::Left, point)),
+            EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::Click(MouseButton::Left, point)),
+        ]);
+    }
+
+    // Device pixel in the middle of a terminal cell (graphical view lines up one to one)
+    fn cell_point(&self, col: u16, row: u16) -> DevicePoint {
+        let origin = self.window.coordinates().viewport.origin;
+        DevicePoint::new(origin.x as f32 + col as f32 + 0.5, origin.y as f32 + row as f32 * 2.0 + 1.0)
+    }
+
+    // Element drawn at a terminal cell in text view (row counted from the top of the page area)
+    fn element_at(&self, col: u16, row: u16) -> Option<usize> {
+        let row = self.tab().scroll + row as usize;
+        self.tab().laid.as_ref()?.hits.iter()
+            .find(|hit| hit.row == row && (hit.col..hit.col + hit.width).contains(&col))
+            .map(|hit| hit.element)
+    }
+
+    // Use an element: list a select's options, flip a checkbox or radio button, else click it
+    fn activate(&mut self, element: usize) {
+        let Some(target) = self.tab().page.as_ref().and_then(|page| page.elements.get(element)) else { return };
+        match target.kind {
+            page::ElementKind::Select if !target.options.is_empty() => {
+                let items = target.options.iter().enumerate()
+                    .map(|(i, option)| (option.clone(), menu::Action::Choose(element, i)))
+                    .collect();
+                let mut menu = menu::Menu::new(naive_fluent(&self.strings, "select"), items, None);
+                menu.selected = target.selected.unwrap_or(0);
+                self.state = UiState::Menu(menu);
+            },
+            page::ElementKind::Checkbox | page::ElementKind::Radio =>
+                self.run_script(webdriver::Purpose::WriteField, CHOOSE_JS, vec![element.into(), serde_json::Value::Null]),
+            _ => {
+                self.activated = true;
+                self.run_script(webdriver::Purpose::Activate, ACTIVATE_JS, vec![element.into()]);
+            }
         }
     }
+
+    // Handle mouse in Base state
+    fn mouse(&mut self, event: MouseEvent) {
+        // Tab strip and status bar aren't page
+        let Some(row) = event.row.checked_sub(TAB_ROWS).filter(|row| *row < self.view_height) else { return };
+        let point = self.cell_point(event.column, row);
+        let button = |button| match button {
+            event::MouseButton::Left => MouseButton::Left,
+            event::MouseButton::Right => MouseButton::Right,
+            event::MouseButton::Middle => MouseButton::Middle,
+        };
+        let events = match event.kind {
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+                let rows = if event.kind == MouseEventKind::ScrollDown { WHEEL_LINES } else { -WHEEL_LINES };
+                // Wheel is for the page's wheel event listeners, scroll_by does the scrolling
+                self.servo.handle_events(vec![
+                    EmbedderEvent::Wheel(WheelDelta { x: 0.0, y: rows as f64, z: 0.0, mode: WheelMode::DeltaLine }, point),
+                ]);
+                self.scroll_by(rows);
+                return;
+            },
+            // Text view: positions don't line up with servo's, so go by what's drawn there
+            _ if !self.graphical => {
+                match (event.kind, self.element_at(event.column, row)) {
+                    (MouseEventKind::Down(event::MouseButton::Left), Some(element)) => {
+                        self.tab_mut().focus = Some(element);
+                        self.activate(element);
+                    },
+                    (MouseEventKind::Down(event::MouseButton::Right), element) =>
+                        self.state = UiState::Menu(self.context_menu(element, None, vec![], None)),
+                    _ => ()
+                }
+                return;
+            },
+            MouseEventKind::Moved | MouseEventKind::Drag(_) => vec![EmbedderEvent::MouseWindowMoveEventClass(point)],
+            MouseEventKind::Down(b) => {
+                self.activated = true;
+                vec![EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::MouseDown(button(b), point))]
+            },
+            MouseEventKind::Up(b) => vec![
+                EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::MouseUp(button(b), point)),
+                EmbedderEvent::MouseWindowEventClass(MouseWindowEvent::Click(button(b), point)),
+            ],
+            _ => return
+        };
+        self.servo.handle_events(events);
+    }
+
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.config.max_fps)
+    }
+
+    // While servo animates, it wants pumping at a steady rate
+    fn next_frame(&self) -> Option<Instant> {
+        self.animating.get().then(|| self.last_frame + self.frame_interval())
+    }
+
+    // Load url in the shown tab, creating its webview the first time
+    fn load(&mut self, url: ServoUrl) {
+        let tab = &mut self.tabs[self.current];
+        let event = if tab.opened {
+            EmbedderEvent::LoadUrl(tab.id, url)
+        } else {
+            tab.opened = true;
+            EmbedderEvent::NewWebView(url, tab.id)
+        };
+        self.servo.handle_events(vec![event]);
+    }
+
+    // Where to draw a text field's editor: over the field as text view drew it, else where servo has it
+    fn field_area(&self, editor: &editor::Editor, content: Rect) -> Rect {
+        let tab = self.tab();
+        let is_field = |focus: &usize| tab.page.as_ref().and_then(|page| page.elements.get(*focus))
+            .is_some_and(|element| matches!(element.kind, page::ElementKind::Input | page::ElementKind::TextArea));
+        let hit = tab.focus.filter(|focus| !self.graphical && is_field(focus))
+            .and_then(|focus| tab.laid.as_ref()?.hits.iter().find(|hit| hit.element == focus && hit.row >= tab.scroll));
+        let (x, y, width, height) = match hit {
+            Some(hit) => (hit.col, (hit.row - tab.scroll).min(u16::MAX as usize) as u16, hit.width, 0),
+            None => {
+                // One device pixel per column, two per row
+                let bounds = editor.bounds;
+                let clamp = |n: i32| n.clamp(0, u16::MAX as i32) as u16;
+                (clamp(bounds.min.x), clamp(bounds.min.y / 2), clamp(bounds.width()), clamp(bounds.height() / 2))
+            }
+        };
+        let width = width.max(FIELD_WIDTH).min(content.width);
+        let height = if editor.multiline() { height.max(FIELD_ROWS) } else { 1 }.min(content.height);
+        Rect::new(content.x + x.min(content.width - width), content.y + y.min(content.height - height), width, height)
+    }
+
+    // Menu for a link or image (if any) and the page. Servo adds the page's own items when it asks for one
+    fn context_menu(&self, element: Option<usize>, title: Option<String>, page_items: Vec<String>, reply: Option<IpcSender<ContextMenuResult>>) -> menu::Menu {
+        let label = |key| naive_fluent(&self.strings, key);
+        let mut items = vec![];
+        let element = element.and_then(|element| self.tab().page.as_ref()?.elements.get(element));
+        if let Some(element) = element {
+            if let Some(url) = element.href.as_deref().and_then(|href| ServoUrl::parse(href).ok()) {
+                if element.kind == page::ElementKind::Image {
+                    items.push((label("menu-view-image"), menu::Action::View(url.clone())));
+                    items.push((label("menu-copy-image"), menu::Action::Copy(url.as_str().to_string())));
+                    items.push((label("menu-save-image"), menu::Action::Save(url)));
+                } else {
+                    items.push((label("menu-open-tab"), menu::Action::OpenTab(url.clone())));
+                    items.push((label("menu-copy-link"), menu::Action::Copy(url.as_str().to_string())));
+                    items.push((label("menu-save-link"), menu::Action::Save(url)));
+                }
+            }
+        }
+        if let Some(url) = self.tab().url() {
+            items.push((label("menu-copy-page"), menu::Action::Copy(url.as_str().to_string())));
+        }
+        items.extend(page_items.into_iter().enumerate().map(|(i, item)| (item, menu::Action::Page(i))));
+        menu::Menu::new(title.unwrap_or_else(|| label("menu")), items, reply)
+    }
+
+    fn menu_action(&mut self, action: menu::Action) {
+        match action {
+            menu::Action::OpenTab(url) => { self.new_tab(Some(url)); }, // Stays in the background
+            menu::Action::View(url) => self.load(url),
+            menu::Action::Copy(text) => self.copy(text),
+            menu::Action::Save(url) => self.ask_download(url, self.tab().id),
+            menu::Action::Choose(element, option) =>
+                self.run_script(webdriver::Purpose::WriteField, CHOOSE_JS, vec![element.into(), option.into()]),
+            menu::Action::Page(_) => () // Servo's to handle
+        }
+    }
+
+    fn copy(&mut self, text: String) {
+        let key = if self.clipboard.set(text).is_ok() { "copied" } else { "copy-failed" };
+        self.tab_mut().bar_state = BarState::notice(naive_fluent(&self.strings, key));
+    }
+
+    // Ask where to save url, which tab's page will fetch
+    fn ask_download(&mut self, url: ServoUrl, tab: TopLevelBrowsingContextId) {
+        let path = download::suggest(&self.config.downloads, &url);
+        self.state = UiState::SaveAs(Input::new(path.display().to_string()), url, tab);
+    }
+
+    fn start_download(&mut self, url: ServoUrl, path: PathBuf, tab: TopLevelBrowsingContextId) {
+        let mut args = FluentArgs::new();
+        args.set("url", url.as_str());
+        self.tab_mut().bar_state = BarState::notice(naive_fluent_args(&self.strings, "saving", &args));
+        let id = self.downloads.iter().map(|download| download.id + 1).max().unwrap_or(0);
+        let mut download = download::Download::new(id, url, path, tab);
+        download.start(self.user_agent.clone(), self.notifier());
+        self.downloads.push(download);
+        self.store_downloads();
+    }
+
+    // For download threads to report back through
+    fn notifier(&self) -> impl Fn(usize, usize, download::Update) + Send + 'static {
+        let sender = self.wakes.clone();
+        move |id, attempt, update| { let _ = sender.send(Wake::Download(id, attempt, update)); } // Fails only if run_app is done
+    }
+
+    // A download thread has news
+    fn download_update(&mut self, id: usize, attempt: usize, update: download::Update) {
+        let scripts = self.scripts.enabled();
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) else { return };
+        if download.attempt != attempt || download.page || download.state != download::State::Active {
+            return; // From a thread given up on
+        }
+        let mut args = FluentArgs::new();
+        args.set("url", download.url.as_str());
+        let notice = match update {
+            download::Update::Progress(received, total) => {
+                (download.received, download.total) = (received, total);
+                return;
+            },
+            download::Update::Done => {
+                download.state = download::State::Done;
+                args.set("path", download.path.display().to_string());
+                naive_fluent_args(&self.strings, "saved", &args)
+            },
+            // The page may be let in where we weren't
+            download::Update::Refused(_) if scripts && download.tab.is_some() => {
+                (download.page, download.received) = (true, 0);
+                return;
+            },
+            download::Update::Failed(e) | download::Update::Refused(e) => {
+                args.set("error", e.clone());
+                download.state = download::State::Failed(e);
+                naive_fluent_args(&self.strings, "save-failed", &args)
+            },
+        };
+        self.tab_mut().bar_state = BarState::notice(notice);
+        self.store_downloads();
+    }
+
+    // Ask for the next chunk of each download the page fetches, if its tab is shown
+    fn pump_downloads(&mut self) {
+        let current = self.tab().id;
+        for download in self.downloads.iter_mut()
+            .filter(|download| download.state == download::State::Active && download.page && !download.fetching && download.tab == Some(current))
+        {
+            download.fetching = true;
+            self.scripts.run(current, webdriver::Purpose::Save(download.id), FETCH_JS, download.next_chunk());
+        }
+    }
+
+    // A chunk of a download arrived
+    fn save(&mut self, id: usize, result: Result<serde_json::Value, String>) {
+        let Some(download) = self.downloads.iter_mut().find(|download| download.id == id) else { return };
+        download.fetching = false;
+        if download.state != download::State::Active || !download.page {
+            return; // Cancelled or started again meanwhile
+        }
+        let result = result.and_then(|value| {
+            let data = BASE64.decode(value["data"].as_str().unwrap_or("")).map_err(|e| e.to_string())?;
+            download.receive(&data, value["total"].as_u64(), value["whole"].as_bool().unwrap_or(true)).map_err(|e| e.to_string())
+        });
+        let mut args = FluentArgs::new();
+        args.set("url", download.url.as_str());
+        let notice = match result {
+            Ok(()) if download.state == download::State::Done => {
+                args.set("path", download.path.display().to_string());
+                naive_fluent_args(&self.strings, "saved", &args)
+            },
+            Ok(()) => return, // More to come
+            Err(e) => {
+                args.set("error", e.clone());
+                download.state = download::State::Failed(e);
+                naive_fluent_args(&self.strings, "save-failed", &args)
+            }
+        };
+        self.tab_mut().bar_state = BarState::notice(notice);
+        self.store_downloads();
+    }
+
+    // Text edited in $EDITOR, if it was changed. Failing says so in the status bar
+    fn edit_text<B: Backend>(&mut self, terminal: &mut Terminal<B>, text: &str) -> Option<String> {
+        let edited = edit_externally(terminal, &self.input, text);
+        self.images.redraw();
+        match edited {
+            Ok(edited) => edited.filter(|edited| edited != text),
+            Err(e) => {
+                let mut args = FluentArgs::new();
+                args.set("error", e.to_string());
+                self.tab_mut().bar_state = BarState::notice(naive_fluent_args(&self.strings, "edit-failed", &args));
+                None
+            }
+        }
+    }
+
+    // Edit a field servo put the cursor in, in $EDITOR, and type the result in. For when scripts can't reach it
+    fn edit_field<B: Backend>(&mut self, terminal: &mut Terminal<B>, editor: &mut editor::Editor) {
+        if let Some(edited) = self.edit_text(terminal, &editor.value()) {
+            let events = editor.replace(&edited);
+            self.servo.handle_events(events);
+        }
+    }
+
+    fn store_downloads(&self) {
+        let _ = download::store(&self.config.profile, &self.downloads); // Only the list is lost; not worth stopping for
+    }
+
+    fn go(&mut self, forward: bool) {
+        if self.tab().can_go(forward) {
+            let direction = if forward { TraversalDirection::Forward(1) } else { TraversalDirection::Back(1) };
+            self.servo.handle_events(vec![EmbedderEvent::Navigation(self.tab().id, direction)]);
+        }
+    }
+
+    // Add a tab after the shown one, loading url in it if given. Returns its index
+    fn new_tab(&mut self, url: Option<ServoUrl>) -> usize {
+        let mut tab = tab::Tab::new(TopLevelBrowsingContextId::new());
+        if let Some(url) = url {
+            tab.opened = true;
+            self.servo.handle_events(vec![EmbedderEvent::NewWebView(url, tab.id)]);
+        }
+        self.tabs.insert(self.current + 1, tab);
+        self.current + 1
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        if let Some(old) = self.tabs.get(self.current).filter(|tab| tab.opened && index != self.current) {
+            self.servo.handle_events(vec![EmbedderEvent::HideWebView(old.id)]);
+        }
+        self.current = index;
+        self.images.clear();
+        let tab = &mut self.tabs[index];
+        tab.laid = None; // So images get asked for again
+        if tab.opened {
+            self.servo.handle_events(vec![EmbedderEvent::FocusWebView(tab.id), EmbedderEvent::ShowWebView(tab.id, true)]);
+            if tab.stale {
+                self.scripts.run(tab.id, webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]);
+            }
+        }
+    }
+
+    // Next or previous tab, wrapping around
+    fn cycle_tab(&mut self, forward: bool) {
+        let count = self.tabs.len();
+        if count > 1 {
+            self.select_tab(if forward { (self.current + 1) % count } else { (self.current + count - 1) % count });
+        }
+    }
+
+    // Forget a tab whose webview is closed or closing. Closing the last one leaves an empty one
+    fn remove_tab(&mut self, index: usize) {
+        let id = self.tabs.remove(index).id;
+        // Nothing left to fetch those the page was fetching
+        let closed = naive_fluent(&self.strings, "download-tab-closed");
+        for download in self.downloads.iter_mut().filter(|download| download.tab == Some(id)) {
+            if download.page && download.state == download::State::Active {
+                download.state = download::State::Failed(closed.clone());
+            }
+            download.tab = None;
+        }
+        if self.tabs.is_empty() {
+            self.tabs.push(tab::Tab::new(TopLevelBrowsingContextId::new()));
+        }
+        if index < self.current {
+            self.current -= 1;
+        } else if index == self.current {
+            self.select_tab(self.current.min(self.tabs.len() - 1));
+        }
+    }
+
+    // Terminal changed size
+    fn resize(&mut self, cols: u16, rows: u16) {
+        let (width, height) = viewport_size(cols, rows);
+        self.window.resize(glue::Coordinates::new(0, 0, width, height, width, height));
+        self.pixels.resize(width, height);
+        let viewport = self.window.coordinates().viewport.to_f32().to_box2d();
+        let mut events = vec![EmbedderEvent::WindowResize];
+        for tab in self.tabs.iter_mut().filter(|tab| tab.opened) {
+            events.push(EmbedderEvent::MoveResizeWebView(tab.id, viewport));
+            // Servo will lay out again, so image sizes and such are stale
+            tab.stale = tab.page.is_some();
+        }
+        self.servo.handle_events(events);
+        if self.tab().stale {
+            self.run_script(webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]);
+        }
+    }
+
+    // When run_app must wake up even if nothing happens
+    fn deadline(&self) -> Option<Instant> {
+        let spinner = matches!(self.tab().bar_state, BarState::UrlLoading(_)).then(|| self.last_frame + SPINNER_INTERVAL);
+        let notice = match self.tab().bar_state { BarState::Notice(_, until) => Some(until), _ => None };
+        let deadline = [self.next_frame(), spinner, notice].into_iter().flatten().min();
+        #[cfg(feature = "debug_mode")]
+        if let Some(flip) = self.debug_display.as_ref().and_then(|d| d.flip) {
+            return Some(deadline.map_or(flip, |deadline| deadline.min(flip)));
+        }
+        deadline
+    }
+
+    // Put the title and URL back where notices have had their time
+    fn expire_notices(&mut self) {
+        let now = Instant::now();
+        for tab in &mut self.tabs {
+            if matches!(tab.bar_state, BarState::Notice(_, until) if until <= now) {
+                tab.bar_state = BarState::None;
+            }
+        }
+    }
+
+    #[cfg(feature = "debug_mode")]
+    fn debug_message(&mut self, message: String) {
+        if let Some(d) = &mut self.debug_display {
+            if d.flip.is_none() { d.flip = debug_display_reset(); }
+            d.queue.push_back(message);
+        }
+    }
+}
+
+// Servo viewport size for a terminal size. One device pixel per column, two per row, so graphical view can draw half blocks
+fn viewport_size(cols: u16, rows: u16) -> (i32, i32) {
+    (cols as i32, rows.saturating_sub(CHROME_ROWS) as i32 * 2)
+}
+
+// Size of a terminal cell in screen pixels, from config or else the terminal (CSI 16t, then TIOCGWINSZ)
+fn cell_size(config: &config::Config, answers: &tty::Answers) -> (u16, u16) {
+    config.cell.or(answers.cell).unwrap_or_else(|| match terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 =>
+            (size.width / size.columns, size.height / size.rows),
+        _ => (10, 20) // Common enough
+    })
+}
+
+// Terminal input gets its own thread so run_app can wait on input and servo at once.
+// It can be paused, so another program (like $EDITOR) can have the terminal.
+struct InputThread {
+    pause: Arc<AtomicBool>, // Asked to stop reading
+    parked: Arc<AtomicBool>, // Has stopped reading
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl InputThread {
+    fn spawn(sender: Sender<Wake>) -> Self {
+        let (pause, parked) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let thread = {
+            let (pause, parked) = (pause.clone(), parked.clone());
+            std::thread::spawn(move || loop {
+                if pause.load(Ordering::SeqCst) {
+                    parked.store(true, Ordering::SeqCst);
+                    std::thread::sleep(INPUT_POLL);
+                    continue;
+                }
+                parked.store(false, Ordering::SeqCst);
+                // Polling, not blocking in read, so a pause is noticed
+                match event::poll(INPUT_POLL) {
+                    Ok(false) => (),
+                    Ok(true) => match event::read() {
+                        Ok(ev) => if sender.send(Wake::Input(ev)).is_err() { break },
+                        Err(_) => break,
+                    },
+                    Err(_) => break,
+                }
+            })
+        };
+        InputThread { pause, parked, thread }
+    }
+
+    // Returns once the thread has stopped reading, or has ended (on a read error) and won't read again
+    fn pause(&self) {
+        self.pause.store(true, Ordering::SeqCst);
+        while !self.parked.load(Ordering::SeqCst) && !self.thread.is_finished() {
+            std::thread::sleep(INPUT_POLL / 5);
+        }
+    }
+
+    fn resume(&self) {
+        self.pause.store(false, Ordering::SeqCst);
+    }
+}
+
+// Hand the terminal to $VISUAL or $EDITOR to edit text. None if it failed or was quit without success
+fn edit_externally<B: Backend>(terminal: &mut Terminal<B>, input: &InputThread, text: &str) -> io::Result<Option<String>> {
+    // The text may be a password, so it goes in a folder only we can open
+    let dir = std::env::temp_dir().join(format!("cuervo-{}-{}", std::process::id(),
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos()));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?; // Fails if it was there already, so not someone else's
+    let _dir = TempDir(dir.clone());
+    let path = dir.join("field.txt");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&path).and_then(|mut file| io::Write::write_all(&mut file, text.as_bytes()))?;
+
+    input.pause();
+    let _restore = Restore { terminal, input }; // However this returns from here
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+
+    // Through the shell, since EDITOR may have arguments in it
+    let editor = std::env::var("VISUAL").or_else(|_| std::env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    let status = Command::new("sh").arg("-c").arg(format!("{editor} \"$1\"")).arg("sh").arg(&path).status();
+    let edited = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path).ok(),
+        _ => None
+    };
+
+    // Editors like to end files with a newline the field didn't have
+    Ok(edited.map(|edited| match edited.strip_suffix('\n') {
+        Some(stripped) if !text.ends_with('\n') => stripped.to_string(),
+        _ => edited
+    }))
+}
+
+// Removes a folder when dropped
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+// Takes the terminal back from another program when dropped
+struct Restore<'a, B: Backend> {
+    terminal: &'a mut Terminal<B>,
+    input: &'a InputThread,
+}
+
+impl<B: Backend> Drop for Restore<'_, B> {
+    fn drop(&mut self) {
+        let _ = enable_raw_mode();
+        let _ = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture);
+        let _ = self.terminal.clear();
+        self.input.resume();
+    }
+}
+
+// Things that wake up run_app
+enum Wake {
+    Input(Event), // From the terminal
+    Servo, // Servo (or a script) has something for us
+    Download(usize, usize, download::Update), // From a download's thread: id, attempt, news
 }
 
 // Handle event loop messages
-struct Waker { // TODO
+struct Waker {
+    sender: Sender<Wake>,
 }
 
 impl EventLoopWaker for Waker {
     // Required methods
     fn clone_box(&self) -> Box<dyn EventLoopWaker> {
-        Box::new(Waker {})
+        Box::new(Waker { sender: self.sender.clone() })
     }
     fn wake(&self) {
+        let _ = self.sender.send(Wake::Servo); // Fails only if run_app is done
     }
 }
 
 // Handle messages from glue.rs
 struct HostHandler {
+    animating: Rc<Cell<bool>>, // Shared with App
 }
 
 impl glue::HostTrait for HostHandler {
-    fn on_animating_changed(&self, _animating: bool) {
+    fn on_animating_changed(&self, animating: bool) {
+        self.animating.set(animating);
     }
 }
 
@@ -147,6 +872,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             "tok" => include_str!("strings/tok.ftl"),
             _ => include_str!("strings/en.ftl")
         };
+        strings.set_use_isolating(false); // Terminals draw the bidi isolation marks as junk
         strings
             .add_resource(
                 FluentResource::try_new(rawstring.to_string())
@@ -155,43 +881,94 @@ fn main() -> Result<(), Box<dyn Error>> {
         strings
     };
 
+    // While nothing else is reading the replies
+    let answers = tty::ask();
+
+    let (wake_sender, wakes) = channel();
+    let input = InputThread::spawn(wake_sender.clone());
+
     // create app and run it
     let app = {
-        let waker = Box::new(Waker{});
+        let waker = Box::new(Waker { sender: wake_sender.clone() });
+        let scripts_waker = waker.clone_box();
         let embed_handler = Box::new(EmbedHandler::new(waker));
+        let config = config::Config::load();
         let size = terminal.size().unwrap();
+        let (width, height) = viewport_size(size.width, size.height);
 
         let connection = Connection::new().expect("Failed to create connection");
         let adapter = connection
             .create_software_adapter()
             .expect("Failed to create adapter");
 
-        // FIXME A rendering context is required, but why?
-        let surface_type = SurfaceType::Generic { size: euclid::Size2D::new(1 as i32, 1 as i32) };
+        // Servo composites into this; graphical view reads it back
+        let surface_type = SurfaceType::Generic { size: euclid::Size2D::new(width, height) };
         let rendering_context = RenderingContext::create(&connection, &adapter, surface_type)
             .expect("Failed to create WR surfman");
-
-        let window = glue::WindowCallbacks::new(
-            Box::new(HostHandler {}),
-            RefCell::new(glue::Coordinates::new(0, 0, size.width as i32, size.height as i32, 1, 1)), // TODO update on resize // FIXME 1x1 framebuffer?
-            1.0/20.0, // TODO pick number less arbitrarily
+        let pixels = pixels::Pixels::new(rendering_context.clone(), width, height);
+        let animating = Rc::new(Cell::new(false));
+
+        // A column is one device pixel and should show a cell's width of CSS pixels.
+        // Rows are two device pixels, so pages look right when cells are twice as high as wide.
+        let cell = cell_size(&config, &answers);
+        let density = 1.0/cell.0 as f32;
+        let window = Rc::new(glue::WindowCallbacks::new(
+            Box::new(HostHandler { animating: animating.clone() }),
+            RefCell::new(glue::Coordinates::new(0, 0, width, height, width, height)),
+            density,
             rendering_context
-        );
+        ));
 
         let user_agent = servo::default_user_agent_string_for(servo::UserAgent::Desktop);
         let mut cuervo_version_iter = VERSION.chars();
         let cuervo_version = cuervo_version_iter.next().unwrap().to_uppercase().collect::<String>()+cuervo_version_iter.as_str();
 
+        let webdriver = config.webdriver.then(webdriver::enable); // Right before servo starts, so the port has little time to be taken
+        let scripts = webdriver::Scripts::new(webdriver.as_ref().and_then(|port| port.as_ref().ok()).copied(), scripts_waker);
+        let user_agent = format!("{user_agent} {cuervo_version} (like w3m)");
         let servo = servo::Servo::new(
             embed_handler,
-            Rc::new(window),
-            Some(format!("{user_agent} {cuervo_version} (like w3m)"), ), // User agent
+            window.clone(),
+            Some(user_agent.clone()),
             servo::compositing::CompositeTarget::Window,
         );
 
-        App::new(strings, servo.browser_id, servo.servo)
+        let mut app = App {
+            state: UiState::Base,
+            strings,
+            tabs: vec![tab::Tab::new(servo.browser_id)],
+            current: 0,
+            servo: servo.servo,
+            window,
+            view_height: 0,
+            pending: None,
+            field: None,
+            activated: false,
+            opener: None,
+            pixels,
+            graphical: !scripts.enabled(), // Pictures are all there is without WebDriver
+            scripts,
+            images: images::Images::new(images::detect(config.images.as_deref(), &answers), cell),
+            downloads: download::load(&config.profile),
+            user_agent,
+            wakes: wake_sender,
+            clipboard: clipboard::Clipboard::new(config.clipboard),
+            density,
+            config,
+            animating,
+            input,
+            last_frame: Instant::now(),
+            #[cfg(feature = "debug_mode")]
+            debug_display: None,
+        };
+        if let Some(Err(e)) = webdriver {
+            let mut args = FluentArgs::new();
+            args.set("error", e.to_string());
+            app.tab_mut().bar_state = BarState::notice(naive_fluent_args(&app.strings, "webdriver-failed", &args));
+        }
+        app
     };
-    let res = run_app(&mut terminal, app);
+    let res = run_app(&mut terminal, app, wakes);
 
     // restore terminal
     disable_raw_mode()?;
@@ -210,66 +987,361 @@ fn main() -> Result<(), Box<dyn Error>> {
 }
 
 // HANDLE EVENTS
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<()> {
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App, wakes: Receiver<Wake>) -> io::Result<()> {
     'run: loop {
-        // Kick to draw
-        terminal.draw(|f| ui(f, &app))?;
+        app.expire_notices();
+
+        // Kick to draw, unless animating and it's too soon
+        if app.next_frame().map_or(true, |frame| frame <= Instant::now()) {
+            let size = terminal.size()?;
+            app.relayout(size.width, size.height);
+            let mut placements = vec![];
+            terminal.draw(|f| placements = ui(f, &app))?;
+            app.images.show(placements)?;
+            app.last_frame = Instant::now();
+        }
 
-        let ev = event::read()?; // FIXME: No good, must pump events
-        let mut sent_event = false;
+        // Sleep until there's input, servo wakes us, or something is due
+        let first = match app.deadline() {
+            Some(deadline) => match wakes.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(wake) => Some(wake),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break 'run,
+            },
+            None => match wakes.recv() {
+                Ok(wake) => Some(wake),
+                Err(_) => break 'run,
+            }
+        };
+        let mut inputs = vec![];
+        for wake in first.into_iter().chain(wakes.try_iter()) {
+            match wake {
+                Wake::Input(ev) => inputs.push(ev),
+                Wake::Servo => (),
+                Wake::Download(id, attempt, update) => app.download_update(id, attempt, update),
+            }
+        }
 
         // Handle events
-        match &mut app.state {
-            UiState::Base =>
-                if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
-                    if key.kind == KeyEventKind::Press {
-                        match key.code {
-                            // Quit
-                            KeyCode::Char('q') => break 'run,
-                            // Go to
-                            KeyCode::Char('g') => app.state = UiState::Goto("https://".into()),
-                            
-                            // Debug mode?!
-                            #[cfg(feature = "debug_mode")]
-                            KeyCode::Char('p') => if modifiers.contains(KeyModifiers::CONTROL) {
-                                app.debug_display = if app.debug_display.is_none() {
-                                    let mut d = DebugMode::default();
-                                    d.flip = Some(std::time::Instant::now() + DEBUG_DISPLAY_FRESH*2);
-                                    d.queue.push_back("Debug display entered (CTRL-P to revert)".to_string()); // Not localized
-                                    Some(d)
-                                } else { None };
+        for ev in inputs {
+            if let Event::Resize(cols, rows) = ev {
+                app.resize(cols, rows);
+                continue;
+            }
+            match &mut app.state {
+                UiState::Base =>
+                    if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
+                        if key.kind == KeyEventKind::Press {
+                            let pending = app.pending.take();
+                            let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+                            let height = app.view_height.max(1) as isize;
+                            match key.code {
+                                // Quit
+                                KeyCode::Char('q') => break 'run,
+                                // Go to. Not g, which starts g g
+                                KeyCode::Char('o') => app.state = UiState::Goto(Input::default()),
+                                // Follow link, here or in a new tab
+                                KeyCode::Char(c @ ('f' | 'F')) => if let (Some(page), Some(laid), false) = (&app.tab().page, &app.tab().laid, app.graphical) {
+                                    if let Some(hints) = hints::Hints::new(page, laid, app.tab().scroll, app.view_height) {
+                                        app.state = UiState::Hint(hints, c == 'F');
+                                    }
+                                },
+                                // Tabs
+                                KeyCode::Char('t') => {
+                                    let index = app.new_tab(None);
+                                    app.select_tab(index);
+                                    app.state = UiState::Goto(Input::default());
+                                },
+                                KeyCode::Char('x') => {
+                                    if app.tab().opened {
+                                        app.servo.handle_events(vec![EmbedderEvent::CloseWebView(app.tab().id)]);
+                                    }
+                                    app.remove_tab(app.current);
+                                },
+                                KeyCode::Char('J') => app.cycle_tab(true),
+                                KeyCode::Char('K') => app.cycle_tab(false),
+                                // Move focus. n, N since j, k scroll
+                                KeyCode::Tab | KeyCode::Char('n') => app.move_focus(true),
+                                KeyCode::BackTab | KeyCode::Char('N') => app.move_focus(false),
+                                // Scroll
+                                KeyCode::Char('j') | KeyCode::Down => app.scroll_by(1),
+                                KeyCode::Char('k') | KeyCode::Up => app.scroll_by(-1),
+                                KeyCode::Char('d') if ctrl => app.scroll_by(height / 2),
+                                KeyCode::Char('u') if ctrl => app.scroll_by(-height / 2),
+                                KeyCode::Char(' ') | KeyCode::PageDown => app.scroll_by(height),
+                                KeyCode::Char('b') | KeyCode::PageUp => app.scroll_by(-height),
+                                KeyCode::Char('g') if pending == Some('g') => app.scroll_home(false),
+                                KeyCode::Char('g') => app.pending = Some('g'),
+                                KeyCode::Char('G') => app.scroll_home(true),
+                                // History
+                                KeyCode::Char('H') => app.go(false),
+                                KeyCode::Char('L') => app.go(true),
+                                KeyCode::Char('r') => { app.servo.handle_events(vec![EmbedderEvent::Reload(app.tab().id)]); },
+                                // Menu for the focused element and page
+                                KeyCode::Char('m') => app.state = UiState::Menu(app.context_menu(app.tab().focus, None, vec![], None)),
+                                // Copy page address, or what's selected in the page
+                                KeyCode::Char('y') => if let Some(url) = app.tab().url() {
+                                    app.copy(url.as_str().to_string());
+                                },
+                                KeyCode::Char('Y') => app.run_script(webdriver::Purpose::Selection, SELECTION_JS, vec![]),
+                                KeyCode::Char('D') => app.state = UiState::Downloads(app.downloads.len().saturating_sub(1)),
+                                // Edit a text field in $EDITOR: the one the page put the cursor in, else the focused one.
+                                // Without scripts, only the one the page put the cursor in, as typed so far
+                                KeyCode::Char('e') if app.scripts.enabled() => {
+                                    let element = app.tab().focus.filter(|_| app.field.is_none());
+                                    app.run_script(webdriver::Purpose::ReadField(element), FIELD_JS, vec![element.into(), serde_json::Value::Null]);
+                                },
+                                KeyCode::Char('e') => match app.field.take() {
+                                    Some(mut editor) => {
+                                        app.edit_field(terminal, &mut editor);
+                                        app.field = Some(editor);
+                                    },
+                                    None => app.tab_mut().bar_state = BarState::notice(naive_fluent(&app.strings, "edit-no-field")),
+                                },
+                                // Activate focused element
+                                KeyCode::Enter => if let Some(focus) = app.tab().focus {
+                                    app.activate(focus);
+                                },
+                                // Type in a text field the page focused
+                                KeyCode::Char('i') => if let Some(editor) = app.field.take() {
+                                    app.state = UiState::Edit(editor);
+                                },
+                                // Switch text/graphical view. Text needs WebDriver
+                                KeyCode::Char('v') if app.scripts.enabled() => {
+                                    app.graphical = !app.graphical;
+                                    app.servo.handle_events(vec![EmbedderEvent::Refresh]);
+                                },
+                                
+                                // Debug mode?!
+                                #[cfg(feature = "debug_mode")]
+                                KeyCode::Char('p') => if modifiers.contains(KeyModifiers::CONTROL) {
+                                    app.debug_display = if app.debug_display.is_none() {
+                                        let mut d = DebugMode::default();
+                                        d.flip = Some(std::time::Instant::now() + DEBUG_DISPLAY_FRESH*2);
+                                        d.queue.push_back("Debug display entered (CTRL-P to revert)".to_string()); // Not localized
+                                        Some(d)
+                                    } else { None };
+                                },
+
+                                _ => {}
+                            }
+                        }
+                    } else if let Event::Mouse(mouse) = ev {
+                        app.mouse(mouse);
+                    },
+                UiState::Goto(input) =>
+                    if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
+                        // Undocumented: CTRL-Q always quits
+                        let press = key.kind == KeyEventKind::Press;
+                        let ctrl = modifiers.intersects(KeyModifiers::CONTROL);
+                        if press && code == KeyCode::Char('q') && ctrl {
+                            break 'run;
+                        }
+                        let accept = code == KeyCode::Enter;
+                        let done = accept || 
+                            // Undocumented: ESC and CTRL-C exit input
+                            (press && (code == KeyCode::Esc || (code == KeyCode::Char('c') && ctrl)));
+
+                        if done {
+                            app.tabs[app.current].bar_state = BarState::None;
+                            if accept {
+                                // FIXME save the url
+                                // Else leave the prompt up to fix the typo
+                                match address::parse(input.value(), &app.config.search) {
+                                    Ok(url) => app.load(url),
+                                    Err(address::Error::Invalid) => {
+                                        app.tabs[app.current].bar_state = BarState::UrlParse(input.value().to_string());
+                                        continue;
+                                    },
+                                    Err(address::Error::Unsupported(scheme)) => {
+                                        let mut args = FluentArgs::new();
+                                        args.set("scheme", scheme);
+                                        app.tabs[app.current].bar_state = BarState::notice(naive_fluent_args(&app.strings, "url-unsupported", &args));
+                                        continue;
+                                    }
+                                }
+                            }
+
+                            app.state = UiState::Base;
+                        } else {
+                            input.handle_event(&Event::Key(key));
+                        }
+                    },
+                UiState::SaveAs(input, _, _) =>
+                    if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        if code == KeyCode::Char('q') && modifiers.contains(KeyModifiers::CONTROL) {
+                            break 'run;
+                        }
+                        match code {
+                            KeyCode::Enter if !input.value().is_empty() => {
+                                let path = PathBuf::from(input.value());
+                                let UiState::SaveAs(_, url, tab) = std::mem::replace(&mut app.state, UiState::Base) else { unreachable!() };
+                                app.start_download(url, path, tab);
                             },
-
-                            _ => {}
+                            KeyCode::Esc => app.state = UiState::Base,
+                            _ => { input.handle_event(&Event::Key(key)); }
                         }
-                    }
-                },
-            UiState::Goto(input) =>
-                if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
-                    // Undocumented: CTRL-Q always quits
-                    let press = key.kind == KeyEventKind::Press;
-                    let ctrl = modifiers.intersects(KeyModifiers::CONTROL);
-                    if press && code == KeyCode::Char('q') && ctrl {
-                        break 'run;
-                    }
-                    let accept = code == KeyCode::Enter;
-                    let done = accept || 
-                        // Undocumented: ESC and CTRL-C exit input
-                        (press && (code == KeyCode::Esc || (code == KeyCode::Char('c') && ctrl)));
-
-                    if done {
-                        if accept {
-                            // FIXME save the url // FIXME handle bad url // FIXME reuse views
-                            let url = servo::servo_url::ServoUrl::parse(input.value()).expect("Not a real url");
-                            sent_event = true;
-                            app.servo.handle_events(vec![EmbedderEvent::NewWebView(url, app.browser_id)]);
+                    },
+                UiState::Downloads(selected) =>
+                    if let Event::Key(key @ KeyEvent { code, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        let mut selected = *selected; // Copied out, as the list changes below
+                        let current = app.tab().id;
+                        let (user_agent, notify) = (app.user_agent.clone(), app.notifier());
+                        let count = app.downloads.len();
+                        let download = app.downloads.get_mut(selected);
+                        match (code, download) {
+                            (KeyCode::Char('j') | KeyCode::Down, _) => selected = (selected + 1).min(count.saturating_sub(1)),
+                            (KeyCode::Char('k') | KeyCode::Up, _) => selected = selected.saturating_sub(1),
+                            (KeyCode::Char('c'), Some(download)) if download.state == download::State::Active =>
+                                download.cancel(),
+                            // Carries on from what's on disk. The tab shown fetches it if the server won't let us
+                            (KeyCode::Char('r'), Some(download)) if matches!(download.state, download::State::Failed(_) | download::State::Cancelled) =>
+                                download.retry(current, user_agent, notify),
+                            // Off the list; the file stays
+                            (KeyCode::Char('x'), Some(download)) if download.state != download::State::Active => {
+                                app.downloads.remove(selected);
+                                selected = selected.min(count.saturating_sub(2));
+                            },
+                            (KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q'), _) => { app.state = UiState::Base; continue },
+                            _ => continue
+                        }
+                        app.state = UiState::Downloads(selected);
+                        app.store_downloads();
+                    },
+                UiState::Hint(hints, new_tab) =>
+                    if let Event::Key(key @ KeyEvent { code, .. }) = ev {
+                        if key.kind == KeyEventKind::Press {
+                            match code {
+                                KeyCode::Char(c) => if let Some(element) = hints.push(c) {
+                                    let new_tab = *new_tab;
+                                    let href = app.tab().page.as_ref()
+                                        .and_then(|page| page.elements.get(element))
+                                        .and_then(|element| element.href.as_deref());
+                                    match href.map(ServoUrl::parse) {
+                                        Some(Ok(url)) if new_tab => { app.new_tab(Some(url)); }, // Stays in the background
+                                        Some(Ok(url)) => app.load(url),
+                                        _ => ()
+                                    }
+                                    app.state = UiState::Base;
+                                },
+                                KeyCode::Backspace => hints.pop(),
+                                // Undocumented: anything else (ESC) gives up
+                                _ => app.state = UiState::Base
+                            }
+                        }
+                    },
+                UiState::Popup(sender, opener) =>
+                    if let Event::Key(key @ KeyEvent { code, .. }) = ev {
+                        if key.kind == KeyEventKind::Press {
+                            let allow = match code {
+                                KeyCode::Char('y') => true,
+                                KeyCode::Char('n') | KeyCode::Esc => false,
+                                _ => continue
+                            };
+                            if allow {
+                                app.opener = *opener;
+                            }
+                            let _ = sender.send(allow);
+                            app.state = UiState::Base;
+                        }
+                    },
+                UiState::Dialog(definition, _, input) =>
+                    if let Event::Key(key @ KeyEvent { code, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        // Which button, if this key is one
+                        let answer = match (&definition, code) {
+                            (PromptDefinition::YesNo(..), KeyCode::Char('y')) => Some(PromptResult::Primary),
+                            (PromptDefinition::YesNo(..), KeyCode::Char('n')) => Some(PromptResult::Secondary),
+                            (PromptDefinition::YesNo(..), KeyCode::Esc) => Some(PromptResult::Dismissed),
+                            (_, KeyCode::Enter) => Some(PromptResult::Primary),
+                            (_, KeyCode::Esc) => Some(PromptResult::Secondary),
+                            _ => None
+                        };
+                        let Some(answer) = answer else {
+                            if let PromptDefinition::Input(..) = definition {
+                                input.handle_event(&Event::Key(key));
+                            }
+                            continue;
+                        };
+                        let value = input.value().to_string();
+                        let UiState::Dialog(definition, ..) = std::mem::replace(&mut app.state, UiState::Base) else { unreachable!() };
+                        let _ = match definition {
+                            PromptDefinition::Alert(_, sender) => sender.send(()),
+                            PromptDefinition::YesNo(_, sender) | PromptDefinition::OkCancel(_, sender) => sender.send(answer),
+                            PromptDefinition::Input(_, _, sender) => sender.send((answer == PromptResult::Primary).then_some(value)),
+                        };
+                    },
+                UiState::Menu(menu) =>
+                    if let Event::Key(key @ KeyEvent { code, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        let choose = match code {
+                            KeyCode::Char('j') | KeyCode::Down | KeyCode::Tab => { menu.select(true); continue },
+                            KeyCode::Char('k') | KeyCode::Up | KeyCode::BackTab => { menu.select(false); continue },
+                            KeyCode::Home | KeyCode::End => { menu.select_end(code == KeyCode::End); continue },
+                            KeyCode::Enter => !menu.items.is_empty(),
+                            KeyCode::Esc | KeyCode::Char('m') => false,
+                            _ => continue
+                        };
+                        let UiState::Menu(menu) = std::mem::replace(&mut app.state, UiState::Base) else { unreachable!() };
+                        if let Some(action) = menu.finish(choose) {
+                            app.menu_action(action);
+                        }
+                    },
+                UiState::Files(picker) =>
+                    if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        if code == KeyCode::Char('q') && modifiers.contains(KeyModifiers::CONTROL) {
+                            break 'run;
+                        }
+                        if picker.key(code) {
+                            app.state = UiState::Base;
+                            app.run_script(webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]); // Show what was chosen
+                        }
+                    },
+                UiState::Edit(editor) =>
+                    if let Event::Key(key @ KeyEvent { code, modifiers, .. }) = ev {
+                        if key.kind != KeyEventKind::Press {
+                            continue;
+                        }
+                        if code == KeyCode::Char('q') && modifiers.contains(KeyModifiers::CONTROL) {
+                            break 'run;
+                        }
+                        // Carry on in $EDITOR. The field keeps focus in the page, so field.js finds it.
+                        // Without scripts, what's been typed here is edited, and the result typed in
+                        if code == KeyCode::Char('e') && modifiers.contains(KeyModifiers::CONTROL) {
+                            let UiState::Edit(mut editor) = std::mem::replace(&mut app.state, UiState::Base) else { unreachable!() };
+                            if app.scripts.enabled() {
+                                app.run_script(webdriver::Purpose::ReadField(None), FIELD_JS, vec![serde_json::Value::Null, serde_json::Value::Null]);
+                            } else {
+                                app.edit_field(terminal, &mut editor);
+                                app.state = UiState::Edit(editor);
+                            }
+                            continue;
+                        }
+                        // Esc leaves the field. Tab moves to the next one, Enter submits a one line field; servo says if another wants editing
+                        let done = code == KeyCode::Esc || code == KeyCode::Tab || code == KeyCode::BackTab
+                            || (code == KeyCode::Enter && !editor.multiline());
+                        let events = if code == KeyCode::Esc { vec![EmbedderEvent::IMEDismissed] } else { editor.key(key) };
+                        app.servo.handle_events(events);
+                        if done {
+                            app.state = UiState::Base;
+                            app.activated = code == KeyCode::Tab || code == KeyCode::BackTab; // So the field Tab goes to opens too
+                            app.run_script(webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]); // Show what was typed
                         }
-
-                        app.state = UiState::Base;
-                    } else {
-                        input.handle_event(&Event::Key(key));
                     }
-                }
+            }
         }
 
         // Rotate queue for debug display (if any)
@@ -284,22 +1356,244 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
         }
 
         // Pump servo queue
-        if !sent_event {
-            // TODO: Sleep 1ms?
-            app.servo.handle_events(vec![]);
-
-            for (_browser_id, event) in app.servo.get_events() {
-                match event {
-                    EmbedderMsg::LoadComplete => {
-                        // TODO: Display lists I guess?
+        app.servo.handle_events(vec![]);
+        let mut present = false;
+        let mut replies = vec![]; // Can't send while get_events holds servo
+        let mut closed = vec![];
+        let mut popups = vec![];
+        let mut menu = None;
+        let mut save = None;
+        let mut asking = None; // Tab to show, as its page put up a dialog
+        let current = app.tab().id;
+        let viewport = app.window.coordinates().viewport.to_f32().to_box2d();
+
+        for (browser_id, event) in app.servo.get_events() {
+            #[cfg(feature = "debug_mode")] // Show every event in debug display
+            if let Some(d) = &mut app.debug_display {
+                if d.flip.is_none() { d.flip = debug_display_reset(); }
+                d.queue.push_back(format!("{event:?}"));
+            }
+
+            let tab = app.tabs.iter_mut().find(|tab| Some(tab.id) == browser_id);
+            match event {
+                // Links to files servo can't show are saved instead
+                EmbedderMsg::AllowNavigationRequest(pipeline, url) if download::looks_like(&url) => {
+                    replies.push(EmbedderEvent::AllowNavigationResponse(pipeline, false));
+                    if let Some(tab) = browser_id {
+                        save = Some((url, tab));
+                    }
+                },
+                EmbedderMsg::AllowNavigationRequest(pipeline, url) => {
+                    if let Some(tab) = tab {
+                        tab.loading = Some(url);
+                    }
+                    replies.push(EmbedderEvent::AllowNavigationResponse(pipeline, true));
+                },
+                // The page waits for the answer to these
+                EmbedderMsg::Prompt(definition, origin) => match app.state {
+                    UiState::Base | UiState::Hint(..) | UiState::Edit(_) => {
+                        // Often asked from the field's own handlers. What was typed is in the page already; i carries on
+                        if let UiState::Edit(_) = app.state {
+                            let UiState::Edit(editor) = std::mem::replace(&mut app.state, UiState::Base) else { unreachable!() };
+                            app.field = (browser_id == Some(current)).then_some(editor);
+                        }
+                        // Over the page that asked, not whichever is shown
+                        if browser_id.is_some_and(|id| id != current) {
+                            asking = browser_id;
+                        }
+                        let default = match &definition { PromptDefinition::Input(_, default, _) => default.clone(), _ => String::new() };
+                        app.state = UiState::Dialog(definition, origin == PromptOrigin::Untrusted, default.into());
                     },
-                    _=>()
-                }
+                    // Only one question at a time, and nothing half typed or waiting on an answer is thrown away
+                    _ => dismiss(definition),
+                },
+                // Right click in graphical view, or a page asking for its menu
+                EmbedderMsg::ShowContextMenu(sender, title, items) => match app.state {
+                    UiState::Base if browser_id == Some(current) && menu.is_none() => menu = Some((sender, title, items)),
+                    _ => { let _ = sender.send(ContextMenuResult::Dismissed); }
+                },
+                // A page wants a new window
+                EmbedderMsg::AllowOpeningWebView(sender) => match (app.config.popups, &app.state) {
+                    (config::Popups::Always, _) => {
+                        app.opener = browser_id;
+                        let _ = sender.send(true);
+                    },
+                    // Not over a prompt, whose typing would be lost
+                    (config::Popups::Ask, UiState::Base | UiState::Hint(..)) =>
+                        app.state = UiState::Popup(sender, browser_id),
+                    _ => { let _ = sender.send(false); }
+                },
+                EmbedderMsg::WebViewOpened(id) => {
+                    replies.push(EmbedderEvent::MoveResizeWebView(id, viewport));
+                    if id == current {
+                        replies.extend([EmbedderEvent::FocusWebView(id), EmbedderEvent::ShowWebView(id, true)]);
+                    }
+                    // Not one of ours, so it's a popup
+                    if !app.tabs.iter().any(|tab| tab.id == id) {
+                        popups.push(id);
+                    }
+                },
+                EmbedderMsg::WebViewClosed(id) => closed.push(id),
+                EmbedderMsg::LoadStart => if let Some(tab) = tab {
+                    tab.bar_state = BarState::UrlLoading(Instant::now());
+                    if tab.id == current {
+                        app.activated = false;
+                        app.field = None;
+                    }
+                },
+                EmbedderMsg::LoadComplete => if let Some(tab) = tab {
+                    if let BarState::UrlLoading(_) = tab.bar_state {
+                        tab.bar_state = BarState::None;
+                    }
+                    tab.loading = None;
+                    if tab.id == current {
+                        app.scripts.run(tab.id, webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]);
+                    } else {
+                        tab.stale = true;
+                    }
+                },
+                EmbedderMsg::ChangePageTitle(title) => if let Some(tab) = tab {
+                    tab.title = title;
+                },
+                EmbedderMsg::HistoryChanged(history, index) => if let Some(tab) = tab {
+                    tab.history = history;
+                    tab.history_index = index;
+                },
+                // A text field took focus. Edit it now if the user clicked it, else when they ask (so autofocus doesn't take over the keys)
+                EmbedderMsg::ShowIME(kind, text, multiline, bounds) if browser_id == Some(current) => {
+                    let editor = editor::Editor::new(kind, text, multiline, bounds);
+                    match app.state {
+                        UiState::Base | UiState::Edit(_) if app.activated => {
+                            app.activated = false;
+                            app.state = UiState::Edit(editor);
+                        },
+                        _ => app.field = Some(editor),
+                    }
+                },
+                // <input type=file> was clicked
+                EmbedderMsg::SelectFiles(filters, multiple, sender) => match app.state {
+                    UiState::Base if browser_id == Some(current) =>
+                        app.state = UiState::Files(files::Picker::new(&app.config.uploads, filters, multiple, sender)),
+                    _ => { let _ = sender.send(None); }
+                },
+                EmbedderMsg::HideIME if browser_id == Some(current) => {
+                    app.field = None;
+                    if let UiState::Edit(_) = app.state {
+                        app.state = UiState::Base;
+                    }
+                },
+                // Pages copying and pasting, by script or keyboard
+                EmbedderMsg::GetClipboardContents(sender) => {
+                    let pause = app.clipboard.asks_terminal();
+                    if pause { app.input.pause(); }
+                    let _ = sender.send(app.clipboard.get().to_string());
+                    if pause { app.input.resume(); }
+                },
+                EmbedderMsg::SetClipboardContents(text) => { let _ = app.clipboard.set(text); },
+                EmbedderMsg::ReadyToPresent(_) => present = true,
+                _=>()
+            }
+        }
 
-                #[cfg(feature = "debug_mode")] // Show every event in debug display
-                if let Some(d) = &mut app.debug_display {
-                    if d.flip.is_none() { d.flip = debug_display_reset(); }
-                    d.queue.push_back(format!("{event:?}"));
+        if !replies.is_empty() {
+            app.servo.handle_events(replies);
+        }
+
+        if let Some(index) = asking.and_then(|id| app.tabs.iter().position(|tab| tab.id == id)) {
+            app.select_tab(index);
+        }
+        if let Some((sender, title, items)) = menu {
+            app.state = UiState::Menu(app.context_menu(None, title, items, Some(sender)));
+        }
+        if let (Some((url, tab)), UiState::Base) = (save, &app.state) {
+            app.ask_download(url, tab);
+        }
+
+        // Popups go after the tab that opened them, and are shown
+        for id in popups {
+            let mut tab = tab::Tab::new(id);
+            tab.opened = true;
+            tab.opener = app.opener.take();
+            let after = tab.opener.and_then(|opener| app.tabs.iter().position(|tab| tab.id == opener)).unwrap_or(app.current);
+            app.tabs.insert(after + 1, tab);
+            if after < app.current {
+                app.current += 1;
+            }
+            app.select_tab(after + 1);
+        }
+
+        // Closed from inside, by window.close()
+        for id in closed {
+            if let Some(index) = app.tabs.iter().position(|tab| tab.id == id) {
+                app.remove_tab(index);
+            }
+        }
+
+        if present {
+            if app.graphical { app.pixels.read(); }
+            app.servo.present();
+        }
+
+        app.pump_downloads();
+
+        // Collect script results
+        while let Some((tab, purpose, result)) = app.scripts.try_recv() {
+            // Downloads are wanted wherever they came from
+            if let webdriver::Purpose::Save(index) = purpose {
+                app.save(index, result);
+                continue;
+            }
+            // The tab was switched away from since, so this is out of date
+            if tab != app.tab().id {
+                if let (Some(tab), webdriver::Purpose::Snapshot) = (app.tabs.iter_mut().find(|t| t.id == tab), purpose) {
+                    tab.stale = true;
+                }
+                continue;
+            }
+            match (purpose, result) {
+                (webdriver::Purpose::Snapshot, Ok(value)) => {
+                    let fresh = app.tab().page.as_ref().and_then(|page| page.url.as_deref()) != value["url"].as_str();
+                    app.tab_mut().set_page(page::Page::from_json(&value));
+                    app.images.clear();
+                    // Servo loaded something it can't really show (a link without a telling name), so offer to save it
+                    let url = value["url"].as_str().and_then(|url| ServoUrl::parse(url).ok()).filter(|_| fresh);
+                    if let (Some(url), Some(false), UiState::Base) = (url, value["type"].as_str().map(download::renderable), &app.state) {
+                        app.ask_download(url, app.tab().id);
+                    }
+                },
+                (webdriver::Purpose::Activate, Ok(serde_json::Value::Array(center))) => {
+                    // Script gives CSS pixels
+                    if let [Some(x), Some(y)] = [center.first(), center.get(1)].map(|v| v.and_then(serde_json::Value::as_f64)) {
+                        app.click(DevicePoint::new(x as f32 * app.density, y as f32 * app.density));
+                    }
+                },
+                (webdriver::Purpose::Image(element), Ok(serde_json::Value::String(data))) =>
+                    app.images.receive(element, data),
+                (webdriver::Purpose::Selection, Ok(serde_json::Value::String(text))) if !text.is_empty() => app.copy(text),
+                (webdriver::Purpose::Selection, Ok(_)) =>
+                    app.tab_mut().bar_state = BarState::notice(naive_fluent(&app.strings, "copy-nothing")),
+                (webdriver::Purpose::ReadField(element), Ok(serde_json::Value::String(text))) => {
+                    if let Some(edited) = app.edit_text(terminal, &text) {
+                        app.run_script(webdriver::Purpose::WriteField, FIELD_JS, vec![element.into(), edited.into()]);
+                    }
+                },
+                // Not a text field, or none had focus
+                (webdriver::Purpose::ReadField(_), Ok(_)) =>
+                    app.tab_mut().bar_state = BarState::notice(naive_fluent(&app.strings, "edit-no-field")),
+                // Show what was written
+                (webdriver::Purpose::WriteField, Ok(_)) => app.run_script(webdriver::Purpose::Snapshot, SNAPSHOT_JS, vec![]),
+                (_, Ok(_)) => (), // Image unavailable, element gone
+                // Anyone could reach it, so nothing more goes through it
+                (_, Err(e)) if webdriver::exposed() && app.scripts.enabled() => {
+                    app.scripts.disable();
+                    app.graphical = true;
+                    let mut args = FluentArgs::new();
+                    args.set("error", e);
+                    app.tab_mut().bar_state = BarState::notice(naive_fluent_args(&app.strings, "webdriver-failed", &args));
+                },
+                (_, Err(_err)) => {
+                    #[cfg(feature = "debug_mode")]
+                    app.debug_message(_err);
                 }
             }
         }
@@ -315,7 +1609,7 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> io::Result<(
             }
         }
 
-        // TODO: Sleep 1ms
+        let _ = wakes.recv_timeout(Duration::from_millis(10));
         app.servo.handle_events(vec![]);
     }
     app.servo.deinit();
@@ -332,21 +1626,103 @@ fn naive_fluent(strings: &FluentBundle<FluentResource>, key:&str) -> String {
     ).to_string()
 }
 
+fn naive_fluent_args(strings: &FluentBundle<FluentResource>, key:&str, args:&FluentArgs) -> String {
+    let mut trash:Vec<FluentError> = Default::default();
+    strings.format_pattern(
+        strings.get_message(key).unwrap().value().unwrap(),
+        Some(args),
+        &mut trash
+    ).to_string()
+}
+
 // DRAW
-fn ui(f: &mut Frame, app: &App) {
+// Returns images to be drawn over the frame
+fn ui(f: &mut Frame, app: &App) -> Vec<images::Placement> {
+    let mut placements = vec![];
     let area = f.area();
+    let tab = app.tab();
 
-    let vertical = Layout::vertical([Constraint::Percentage(100)]);
-    let [content] = vertical.areas(area);
+    let vertical = Layout::vertical([Constraint::Length(TAB_ROWS), Constraint::Min(0), Constraint::Length(STATUS_ROWS)]);
+    let [strip, content, status] = vertical.areas(area);
 
-    let intro = Paragraph::new(naive_fluent(&app.strings, "welcome"))
-        //.centered()
-        .wrap(Wrap { trim: false });
+    // Tab strip: number and title of each, starting far enough along that the shown one fits
+    let labels:Vec<Span> = app.tabs.iter().enumerate().map(|(i, tab)| {
+        let label:String = match tab.label() {
+            "" => naive_fluent(&app.strings, "tab-new"),
+            label => label.chars().take(TAB_WIDTH).collect(),
+        };
+        let style = if i == app.current { Style::new().reversed() } else { Style::new() };
+        // Popups say which tab opened them
+        let opener = tab.opener.and_then(|opener| app.tabs.iter().position(|tab| tab.id == opener))
+            .map_or(String::new(), |opener| format!("←{}", opener + 1));
+        Span::styled(format!(" {}{opener} {label} ", i + 1), style)
+    }).collect();
+    let mut first = 0;
+    while first < app.current && labels[first..=app.current].iter().map(Span::width).sum::<usize>() > strip.width as usize {
+        first += 1;
+    }
+    f.render_widget(Line::from(labels[first..].to_vec()), strip);
+
+    if app.graphical && tab.opened {
+        app.pixels.render(content, f.buffer_mut());
+    } else if let Some(laid) = &tab.laid {
+        laid.render(tab.scroll, content, f.buffer_mut());
+        if let Some(focus) = tab.focus {
+            laid.highlight(focus, tab.scroll, content, f.buffer_mut());
+        }
+        if matches!(app.state, UiState::Base) {
+            placements = app.images.prepare(laid.pictures_in(tab.scroll, content), f.buffer_mut());
+        }
+    } else {
+        let intro = Paragraph::new(naive_fluent(&app.strings, "welcome"))
+            //.centered()
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(intro, content);
+    }
 
-    f.render_widget(intro, content);
+    if let UiState::Hint(hints, _) = &app.state {
+        hints.render(tab.scroll, content, f.buffer_mut());
+    }
 
-    if let UiState::Goto(input) = &app.state {
-        let block = Block::bordered().title(naive_fluent(&app.strings, "goto"));
+    // Status bar: which ways history can go, then what's happening, then how far down the page is
+    let percent = app.scroll_percent().map(|percent| format!(" {percent}%")).unwrap_or_default();
+    let [left, right] = Layout::horizontal([Constraint::Min(0), Constraint::Length(percent.len() as u16)]).areas(status);
+    let arrow = |symbol, on| Span::styled(symbol, if on { Style::new() } else { Style::new().dark_gray() });
+    let mut bar = vec![arrow("◀", tab.can_go(false)), arrow("▶", tab.can_go(true)), Span::raw(" ")];
+    let url = tab.url().map_or("", ServoUrl::as_str);
+    let mut args = FluentArgs::new();
+    match &tab.bar_state {
+        BarState::UrlParse(text) => {
+            args.set("url", text.as_str());
+            bar.push(Span::raw(naive_fluent_args(&app.strings, "url-parse", &args)).red());
+        },
+        BarState::UrlLoading(since) => {
+            let frame = (since.elapsed().as_millis() / SPINNER_INTERVAL.as_millis()) as usize % SPINNER.len();
+            args.set("url", url);
+            bar.push(Span::raw(format!("{} ", SPINNER[frame])).yellow());
+            bar.push(Span::raw(naive_fluent_args(&app.strings, "status-loading", &args)));
+        },
+        BarState::Notice(notice, _) => bar.push(Span::raw(notice.as_str())),
+        BarState::None => match tab.title.as_deref().filter(|title| !title.is_empty()) {
+            Some(title) => {
+                args.set("title", title);
+                args.set("url", url);
+                bar.push(Span::raw(naive_fluent_args(&app.strings, "status-page", &args)));
+            },
+            None => bar.push(Span::raw(url)),
+        }
+    }
+    f.render_widget(Line::from(bar), left);
+    f.render_widget(Paragraph::new(percent), right);
+
+    let prompt = match &app.state {
+        UiState::Goto(input) => Some(("goto", input)),
+        UiState::SaveAs(input, ..) => Some(("save-as", input)),
+        _ => None
+    };
+    if let Some((title, input)) = prompt {
+        let block = Block::bordered().title(naive_fluent(&app.strings, title));
         let area = centered_rect(60, 20, area);
         let area = Rect {height:3, ..area}; // Dont actually want relative height
 
@@ -372,17 +1748,127 @@ fn ui(f: &mut Frame, app: &App) {
             ))
     }
 
+    if let UiState::Popup(_, opener) = &app.state {
+        let mut args = FluentArgs::new();
+        args.set("tab", opener.and_then(|opener| app.tabs.iter().position(|tab| tab.id == opener)).map_or(0, |opener| opener + 1));
+        let question = Paragraph::new(naive_fluent_args(&app.strings, "popup-ask", &args))
+            .block(Block::bordered().title(naive_fluent(&app.strings, "popup")))
+            .wrap(Wrap { trim: true });
+        let area = centered_rect(60, 20, area);
+        let area = Rect {height:4, ..area};
+        f.render_widget(Clear, area);
+        f.render_widget(question, area);
+    }
+
+    if let UiState::Dialog(definition, untrusted, input) = &app.state {
+        let (message, keys) = match definition {
+            PromptDefinition::Alert(message, _) => (message, "dialog-alert-keys"),
+            PromptDefinition::YesNo(message, _) => (message, "dialog-yes-no-keys"),
+            PromptDefinition::OkCancel(message, _) | PromptDefinition::Input(message, _, _) => (message, "dialog-ok-cancel-keys"),
+        };
+        let title = naive_fluent(&app.strings, if *untrusted { "dialog-page" } else { "dialog" });
+        let block = Block::bordered().title(title);
+        let area = centered_rect(60, 20, area);
+
+        // Message, then a line to type in for prompt(), then which keys do what
+        let width = area.width.saturating_sub(2).max(1) as usize;
+        let message_rows = message.lines().map(|line| line.chars().count().div_ceil(width).max(1)).sum::<usize>() as u16;
+        let input_rows = matches!(definition, PromptDefinition::Input(..)) as u16;
+        let area = Rect { height: (message_rows + input_rows + 3).min(f.area().height), ..area };
+        let inner = block.inner(area);
+        let [message_area, input_area, keys_area] = Layout::vertical([
+            Constraint::Min(0), Constraint::Length(input_rows), Constraint::Length(1)
+        ]).areas(inner);
+
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+        f.render_widget(Paragraph::new(message.as_str()).wrap(Wrap { trim: false }), message_area);
+        f.render_widget(Paragraph::new(naive_fluent(&app.strings, keys)).dark_gray(), keys_area);
+        if input_rows > 0 {
+            let scroll_amount = input.visual_scroll(input_area.width.max(1) as usize - 1);
+            f.render_widget(Paragraph::new(input.value()).reversed().scroll((0, scroll_amount as u16)), input_area);
+            f.set_cursor_position((
+                input_area.x + (input.visual_cursor().max(scroll_amount) - scroll_amount) as u16,
+                input_area.y,
+            ));
+        }
+    }
+
+    if let UiState::Menu(menu) = &app.state {
+        menu.render(content, f.buffer_mut());
+    }
+
+    if let UiState::Edit(editor) = &app.state {
+        editor.render(app.field_area(editor, content), f);
+    }
+
+    if let UiState::Downloads(selected) = &app.state {
+        let block = Block::bordered().title(naive_fluent(&app.strings, "downloads"));
+        let area = centered_rect(80, 80, content);
+        let inner = block.inner(area);
+        let [list_area, keys_area] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+        let rows = list_area.height as usize;
+        let scroll = (selected + 1).saturating_sub(rows);
+        let lines:Vec<Line> = app.downloads.iter().enumerate().skip(scroll).take(rows).map(|(i, download)| {
+            // How far along, then where it's going, then what went wrong if anything did
+            let (status, style) = match &download.state {
+                download::State::Active => (download.percent().map_or_else(|| size(download.received), |percent| format!("{percent}%")), Style::new().yellow()),
+                download::State::Done => (naive_fluent(&app.strings, "download-done"), Style::new().green()),
+                download::State::Failed(_) => (naive_fluent(&app.strings, "download-failed"), Style::new().red()),
+                download::State::Cancelled => (naive_fluent(&app.strings, "download-cancelled"), Style::new().dark_gray()),
+            };
+            let mut line = vec![Span::styled(format!("{status:>9} "), style), Span::raw(download.path.display().to_string())];
+            if let download::State::Failed(error) = &download.state {
+                line.push(Span::raw(format!(" ({error})")).red());
+            }
+            let line = Line::from(line);
+            if i == *selected { line.reversed() } else { line }
+        }).collect();
+
+        f.render_widget(Clear, area);
+        f.render_widget(block, area);
+        if lines.is_empty() {
+            f.render_widget(Paragraph::new(naive_fluent(&app.strings, "downloads-none")).dark_gray(), list_area);
+        } else {
+            f.render_widget(Paragraph::new(lines), list_area);
+        }
+        f.render_widget(Paragraph::new(naive_fluent(&app.strings, "downloads-keys")).dark_gray(), keys_area);
+    }
+
+    if let UiState::Files(picker) = &app.state {
+        let footer = if picker.outside.is_some() {
+            let mut args = FluentArgs::new();
+            args.set("root", picker.root().display().to_string());
+            naive_fluent_args(&app.strings, "files-outside", &args)
+        } else {
+            naive_fluent(&app.strings, if picker.multiple() { "files-keys-multiple" } else { "files-keys" })
+        };
+        picker.render(centered_rect(80, 80, content), f.buffer_mut(), &footer);
+    }
+
     #[cfg(feature = "debug_mode")]
     if let Some(d) = &app.debug_display {
         if let Some(text) = d.queue.front() {
             let text = format!("{text} ({})", d.queue.len());
             let bar = Paragraph::new(text.clone());
             let mut area = content;
-            area.y = area.height-1;
+            area.y = content.bottom()-1;
             area.height=1;
             f.render_widget(bar, area);
         }
     }
+
+    placements
+}
+
+// Byte count, for people
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{bytes} B"),
+        1_000..1_000_000 => format!("{:.1} kB", bytes as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.1} MB", bytes as f64 / 1e6),
+        _ => format!("{:.1} GB", bytes as f64 / 1e9),
+    }
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -0,0 +1,82 @@
+// Context menus: things to do with a link, image or page, plus any items the page itself offers.
+// Also lists the options of a select.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Widget},
+};
+use servo::embedder_traits::ContextMenuResult;
+use servo::ipc_channel::ipc::IpcSender;
+use servo::servo_url::ServoUrl;
+
+pub enum Action {
+    OpenTab(ServoUrl),
+    Copy(String), // Link or page address
+    Save(ServoUrl),
+    View(ServoUrl), // Image, in this tab
+    Page(usize), // Index into the items servo sent
+    Choose(usize, usize), // Element index of a select, and which option
+}
+
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<(String, Action)>, // Label, and what choosing it does
+    pub selected: usize,
+    pub reply: Option<IpcSender<ContextMenuResult>>, // Set if servo asked for this menu
+}
+
+impl Menu {
+    pub fn new(title: String, items: Vec<(String, Action)>, reply: Option<IpcSender<ContextMenuResult>>) -> Self {
+        Menu { title, items, selected: 0, reply }
+    }
+
+    /// Move the selection, wrapping around
+    pub fn select(&mut self, forward: bool) {
+        let count = self.items.len().max(1);
+        self.selected = if forward { (self.selected + 1) % count } else { (self.selected + count - 1) % count };
+    }
+
+    /// Select the first or last item
+    pub fn select_end(&mut self, last: bool) {
+        self.selected = if last { self.items.len().saturating_sub(1) } else { 0 };
+    }
+
+    /// Close the menu, choosing the selected item (or nothing). Tells servo what happened, and returns what's left to do
+    pub fn finish(mut self, choose: bool) -> Option<Action> {
+        let action = choose.then(|| self.items.swap_remove(self.selected).1);
+        if let Some(reply) = self.reply {
+            let _ = reply.send(match action {
+                Some(Action::Page(i)) => ContextMenuResult::Selected(i),
+                Some(_) => ContextMenuResult::Ignored,
+                None => ContextMenuResult::Dismissed,
+            });
+        }
+        action.filter(|action| !matches!(action, Action::Page(_)))
+    }
+
+    /// Draw as a box in the middle of `area`, sized to fit. Long lists scroll to keep the selection shown
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        let width = self.items.iter().map(|(label, _)| label.chars().count())
+            .chain([self.title.chars().count()])
+            .max().unwrap_or(0) as u16 + 4;
+        let width = width.min(area.width);
+        let height = self.items.len().saturating_add(2).min(area.height as usize) as u16;
+        let menu = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        let rows = height.saturating_sub(2) as usize;
+        let scroll = (self.selected + 1).saturating_sub(rows);
+        let lines:Vec<Line> = self.items.iter().enumerate().skip(scroll).take(rows).map(|(i, (label, _))| {
+            let style = if i == self.selected { Style::new().reversed() } else { Style::new() };
+            Line::styled(format!(" {label} "), style)
+        }).collect();
+        Clear.render(menu, buf);
+        Paragraph::new(lines).block(Block::bordered().title(self.title.as_str())).render(menu, buf);
+    }
+}
@@ -0,0 +1,433 @@
+// Page model and text renderer.
+// A Page is the block structure of a document, as reported by scripts/snapshot.js.
+// Laying it out at a given terminal width gives Lines, which can be drawn into a buffer.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use serde_json::Value;
+
+use crate::images::Placement;
+
+const LINK_STYLE:Style = Style::new().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED);
+const CONTROL_STYLE:Style = Style::new().fg(Color::Yellow);
+const FOCUS_STYLE:Style = Style::new().add_modifier(Modifier::REVERSED);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind { Paragraph, Heading(u8), ListItem(u8), Preformatted, Quote, Rule, Image }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElementKind { Link, Image, Button, Input, TextArea, Select, Checkbox, Radio }
+
+#[derive(Debug, Clone)]
+pub struct TextSpan {
+    pub text: String,
+    pub element: Option<usize>, // Index into Page::elements
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub spans: Vec<TextSpan>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub kind: ElementKind,
+    pub href: Option<String>, // Link target, or image source
+    pub rect: [f32; 4], // x, y, width, height in CSS pixels, relative to the document
+    pub checked: bool, // Checkboxes and radio buttons
+    pub options: Vec<String>, // Selects
+    pub selected: Option<usize>, // Index into options
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub blocks: Vec<Block>,
+    pub elements: Vec<Element>,
+}
+
+/// Part of a laid out line that belongs to an element
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub row: usize,
+    pub col: u16,
+    pub width: u16,
+    pub element: usize,
+}
+
+/// A Page laid out at some width
+#[derive(Debug, Default)]
+pub struct Lines {
+    pub width: u16,
+    pub lines: Vec<Line<'static>>,
+    pub hits: Vec<Hit>,
+    pub pictures: Vec<(usize, Placement)>, // Space left for images, by row
+}
+
+impl BlockKind {
+    // Text before the first and later lines of the block
+    fn prefix(&self) -> (String, String) {
+        match self {
+            BlockKind::ListItem(depth) => {
+                let indent = "  ".repeat(*depth as usize);
+                (format!("{indent}• "), format!("{indent}  "))
+            },
+            BlockKind::Quote => ("│ ".into(), "│ ".into()),
+            _ => (String::new(), String::new()),
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            BlockKind::Heading(1|2) => Style::new().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            BlockKind::Heading(_) => Style::new().add_modifier(Modifier::BOLD),
+            BlockKind::Quote => Style::new().add_modifier(Modifier::ITALIC),
+            _ => Style::new(),
+        }
+    }
+}
+
+impl Element {
+    /// Can the user move to this with Tab?
+    pub fn focusable(&self) -> bool {
+        match self.kind {
+            ElementKind::Link => self.href.is_some(),
+            ElementKind::Image => false,
+            _ => true,
+        }
+    }
+
+    /// How a checkbox or radio button is drawn
+    fn glyph(&self) -> Option<&'static str> {
+        match (self.kind, self.checked) {
+            (ElementKind::Checkbox, true) => Some("[x]"),
+            (ElementKind::Checkbox, false) => Some("[ ]"),
+            (ElementKind::Radio, true) => Some("(*)"),
+            (ElementKind::Radio, false) => Some("( )"),
+            _ => None,
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self.kind {
+            ElementKind::Link | ElementKind::Image => LINK_STYLE,
+            _ => CONTROL_STYLE,
+        }
+    }
+}
+
+impl Page {
+    /// Read the object returned by snapshot.js
+    pub fn from_json(value: &Value) -> Option<Page> {
+        let text = |v:&Value| v.as_str().filter(|s| !s.is_empty()).map(str::to_string);
+        let elements:Vec<Element> = value["elements"].as_array()?.iter().map(|element| {
+            let mut rect = [0.0; 4];
+            for (i, v) in element["rect"].as_array().into_iter().flatten().take(4).enumerate() {
+                rect[i] = v.as_f64().unwrap_or(0.0) as f32;
+            }
+            Element {
+                kind: match element["kind"].as_str() {
+                    Some("img") => ElementKind::Image,
+                    Some("button") => ElementKind::Button,
+                    Some("input") => ElementKind::Input,
+                    Some("textarea") => ElementKind::TextArea,
+                    Some("select") => ElementKind::Select,
+                    Some("checkbox") => ElementKind::Checkbox,
+                    Some("radio") => ElementKind::Radio,
+                    _ => ElementKind::Link,
+                },
+                href: text(&element["href"]),
+                rect,
+                checked: element["checked"].as_bool().unwrap_or(false),
+                options: element["options"].as_array().into_iter().flatten()
+                    .map(|option| option.as_str().unwrap_or("").to_string()).collect(),
+                selected: element["selected"].as_u64().map(|i| i as usize),
+            }
+        }).collect();
+        let blocks = value["blocks"].as_array()?.iter().map(|block| {
+            let level = block["level"].as_u64().unwrap_or(0) as u8;
+            let kind = match block["kind"].as_str() {
+                Some("h") => BlockKind::Heading(level),
+                Some("li") => BlockKind::ListItem(level.saturating_sub(1)),
+                Some("pre") => BlockKind::Preformatted,
+                Some("quote") => BlockKind::Quote,
+                Some("hr") => BlockKind::Rule,
+                Some("img") => BlockKind::Image,
+                _ => BlockKind::Paragraph,
+            };
+            let spans = block["spans"].as_array().into_iter().flatten().map(|span| {
+                let element = span["el"].as_u64().map(|i| i as usize);
+                let glyph = element.and_then(|i| elements.get(i)).and_then(Element::glyph);
+                TextSpan {
+                    text: glyph.unwrap_or(span["text"].as_str().unwrap_or("")).to_string(),
+                    element,
+                }
+            }).collect();
+            Block { kind, spans }
+        }).collect();
+
+        Some(Page { title: text(&value["title"]), url: text(&value["url"]), blocks, elements })
+    }
+
+    // Size in cells of an image block, if it's to be drawn as an image
+    fn picture_size(&self, block: &Block, width: u16, cell: Option<(f32, f32)>) -> Option<(u16, u16)> {
+        let (cell_width, cell_height) = cell?;
+        let element = self.elements.get(block.spans.first()?.element?)?;
+        let cols = (element.rect[2] / cell_width).round().min(width as f32) as u16;
+        let rows = (element.rect[3] / cell_height).round() as u16;
+        (cols > 0 && rows > 0).then_some((cols, rows))
+    }
+
+    /// Lay out for a terminal `width` cells wide. If images can be drawn, `cell` is the size of a cell in CSS pixels.
+    pub fn layout(&self, width: u16, cell: Option<(f32, f32)>) -> Lines {
+        let mut out = Lines { width, ..Default::default() };
+        let mut previous:Option<BlockKind> = None;
+
+        for block in &self.blocks {
+            let picture = if block.kind == BlockKind::Image { self.picture_size(block, width, cell) } else { None };
+            if block.kind != BlockKind::Rule && picture.is_none() && block.spans.iter().all(|s| s.text.trim().is_empty()) {
+                continue;
+            }
+            // Blank line between blocks, except inside a list
+            match (previous, block.kind) {
+                (None, _) | (Some(BlockKind::ListItem(_)), BlockKind::ListItem(_)) => (),
+                _ => out.lines.push(Line::default()),
+            }
+            previous = Some(block.kind);
+
+            match block.kind {
+                BlockKind::Rule => out.lines.push(Line::from("─".repeat(width as usize))),
+                BlockKind::Preformatted => out.preformatted(block, &self.elements),
+                BlockKind::Image => out.image(block, picture),
+                _ => out.wrap(block, &self.elements),
+            }
+        }
+        out
+    }
+}
+
+impl Lines {
+    fn hit(&mut self, element: Option<usize>, col: u16, width: u16) {
+        let Some(element) = element else { return };
+        let row = self.lines.len() - 1;
+        // Words of one link on one row are one hit
+        if let Some(last) = self.hits.last_mut() {
+            if last.row == row && last.element == element && last.col.saturating_add(last.width).saturating_add(1) >= col {
+                last.width = col.saturating_add(width) - last.col;
+                return;
+            }
+        }
+        self.hits.push(Hit { row, col, width, element });
+    }
+
+    // Word wrap a block at our width. Words wider than a line are broken where it ends
+    fn wrap(&mut self, block: &Block, elements: &[Element]) {
+        let (first, rest) = block.kind.prefix();
+        let indent = width_of(&Span::raw(rest.clone()));
+        let room = self.width.saturating_sub(indent).max(1); // Widest a word can be
+        let base = block.kind.style();
+
+        self.lines.push(Line::from(first.clone()));
+        let mut col = width_of(&Span::raw(first));
+        let mut space = false; // Is there whitespace before the next word?
+
+        for span in &block.spans {
+            let style = match span.element.and_then(|i| elements.get(i)) {
+                Some(element) => base.patch(element.style()),
+                None => base
+            };
+            for (n, text) in span.text.split('\n').enumerate() {
+                if n > 0 { // <br>
+                    self.lines.push(Line::from(rest.clone()));
+                    col = indent;
+                    space = false;
+                }
+                for (i, word) in text.split(|c:char| c.is_whitespace()).enumerate() {
+                    if i > 0 { space = true; }
+                    if word.is_empty() { continue; }
+                    for piece in pieces(word, room) {
+                        let piece = Span::styled(piece, style);
+                        let piece_width = width_of(&piece);
+
+                        let gap = (space && col > indent) as u16;
+                        if col > indent && col.saturating_add(gap).saturating_add(piece_width) > self.width {
+                            self.lines.push(Line::from(rest.clone()));
+                            col = indent;
+                        } else if gap > 0 {
+                            // Inside a link the gap is part of the link
+                            let gap_style = if i > 0 { style } else { base };
+                            self.lines.last_mut().unwrap().push_span(Span::styled(" ", gap_style));
+                            col = col.saturating_add(1);
+                        }
+                        space = false;
+
+                        self.hit(span.element, col, piece_width);
+                        self.lines.last_mut().unwrap().push_span(piece);
+                        col = col.saturating_add(piece_width);
+                    }
+                }
+            }
+        }
+    }
+
+    // Copy a block line for line; anything too wide gets cut off when drawn
+    fn preformatted(&mut self, block: &Block, elements: &[Element]) {
+        self.lines.push(Line::default());
+        let mut col = 0;
+        for span in &block.spans {
+            let style = span.element.and_then(|i| elements.get(i)).map_or(Style::new(), Element::style);
+            for (i, text) in span.text.split('\n').enumerate() {
+                if i > 0 {
+                    self.lines.push(Line::default());
+                    col = 0;
+                }
+                if text.is_empty() { continue; }
+                let text = Span::styled(text.replace('\t', "    "), style);
+                let width = width_of(&text);
+                self.hit(span.element, col, width);
+                self.lines.last_mut().unwrap().push_span(text);
+                col = col.saturating_add(width);
+            }
+        }
+    }
+
+    // An image is [alt text], then blank lines to make room if it will be drawn
+    fn image(&mut self, block: &Block, picture: Option<(u16, u16)>) {
+        let alt = block.spans.iter().map(|s| s.text.as_str()).collect::<String>();
+        let alt = Span::styled(format!("[{}]", alt.trim()), Style::new().add_modifier(Modifier::DIM));
+        let width = width_of(&alt);
+        self.lines.push(Line::from(alt));
+        let element = block.spans.first().and_then(|s| s.element);
+        self.hit(element, 0, width);
+
+        if let (Some((cols, rows)), Some(element)) = (picture, element) {
+            self.pictures.push((self.lines.len() - 1, Placement { element, x: 0, y: 0, cols, rows }));
+            for _ in 1..rows {
+                self.lines.push(Line::default());
+            }
+        }
+    }
+
+    /// Mark an element as focused, after drawing from `scroll` into the area
+    pub fn highlight(&self, element: usize, scroll: usize, area: Rect, buf: &mut Buffer) {
+        for hit in self.hits.iter().filter(|hit| hit.element == element) {
+            let Some(row) = hit.row.checked_sub(scroll) else { continue };
+            if row >= area.height as usize || hit.col >= area.width {
+                continue;
+            }
+            let width = hit.width.min(area.width - hit.col);
+            buf.set_style(Rect::new(area.x + hit.col, area.y + row as u16, width, 1), FOCUS_STYLE);
+        }
+    }
+
+    /// First row an element appears on
+    pub fn row_of(&self, element: usize) -> Option<usize> {
+        self.hits.iter().find(|hit| hit.element == element).map(|hit| hit.row)
+    }
+
+    /// Images wholly inside the area when drawn from `scroll`, in screen cells
+    pub fn pictures_in(&self, scroll: usize, area: Rect) -> Vec<Placement> {
+        self.pictures.iter().filter_map(|(row, p)| {
+            let y = row.checked_sub(scroll)?;
+            (y + p.rows as usize <= area.height as usize && p.cols <= area.width)
+                .then_some(Placement { x: area.x + p.x, y: area.y + y as u16, ..*p })
+        }).collect()
+    }
+
+    /// Draw lines starting at `scroll` into the area
+    pub fn render(&self, scroll: usize, area: Rect, buf: &mut Buffer) {
+        for (row, line) in self.lines.iter().skip(scroll).take(area.height as usize).enumerate() {
+            buf.set_line(area.x, area.y + row as u16, line, area.width);
+        }
+    }
+}
+
+// Width in cells, as much as fits in a u16
+fn width_of(span: &Span) -> u16 {
+    span.width().min(u16::MAX as usize) as u16
+}
+
+// A word split into pieces at most `room` cells wide, or whole if it fits
+fn pieces(word: &str, room: u16) -> Vec<String> {
+    if width_of(&Span::raw(word)) <= room {
+        return vec![word.to_string()];
+    }
+    let mut pieces = vec![String::new()];
+    let mut used = 0;
+    for c in word.chars() {
+        let c_width = width_of(&Span::raw(c.to_string()));
+        let piece = pieces.last_mut().unwrap();
+        if !piece.is_empty() && used + c_width > room {
+            pieces.push(String::new());
+            used = 0;
+        }
+        pieces.last_mut().unwrap().push(c);
+        used += c_width;
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Page of one block of one span
+    fn page(kind: &str, text: &str) -> Page {
+        Page::from_json(&json!({"elements": [], "blocks": [{"kind": kind, "level": 1, "spans": [{"text": text}]}]})).unwrap()
+    }
+
+    fn text(lines: &Lines) -> Vec<String> {
+        lines.lines.iter().map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect()).collect()
+    }
+
+    #[test]
+    fn wraps_at_width() {
+        assert_eq!(text(&page("p", "aaa bbb ccc").layout(7, None)), ["aaa bbb", "ccc"]);
+        assert_eq!(text(&page("p", "aaa  bbb\nccc").layout(80, None)), ["aaa bbb", "ccc"]);
+    }
+
+    #[test]
+    fn list_items_indent() {
+        assert_eq!(text(&page("li", "one two three").layout(9, None)), ["• one two", "  three"]);
+    }
+
+    #[test]
+    fn long_words_broken() {
+        let word = "x".repeat(25);
+        assert_eq!(text(&page("p", &format!("a {word} b")).layout(10, None)), ["a", "xxxxxxxxxx", "xxxxxxxxxx", "xxxxx b"]);
+        assert_eq!(text(&page("li", &word).layout(10, None)), ["• xxxxxxxx", "  xxxxxxxx", "  xxxxxxxx", "  x"]);
+    }
+
+    #[test]
+    fn words_wider_than_u16() {
+        let word = "y".repeat(70_000);
+        let lines = page("p", &format!("a {word} b")).layout(80, None);
+        assert_eq!(text(&lines).concat().replace(' ', ""), format!("a{word}b"));
+        assert!(text(&lines).iter().all(|line| line.len() <= 80));
+    }
+
+    #[test]
+    fn wide_characters() {
+        assert_eq!(text(&page("p", "日本語です").layout(4, None)), ["日本", "語で", "す"]);
+        assert_eq!(text(&page("p", "日本").layout(1, None)), ["日", "本"]);
+    }
+
+    #[test]
+    fn link_words_are_one_hit() {
+        let page = Page::from_json(&json!({
+            "elements": [{"kind": "a", "href": "https://example.com/"}],
+            "blocks": [{"kind": "p", "spans": [{"text": "see "}, {"text": "this link", "el": 0}]}],
+        })).unwrap();
+        let lines = page.layout(80, None);
+        assert_eq!(lines.hits.len(), 1);
+        assert_eq!((lines.hits[0].row, lines.hits[0].col, lines.hits[0].width), (0, 4, 9));
+        assert_eq!(lines.row_of(0), Some(0));
+    }
+}
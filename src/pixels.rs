@@ -0,0 +1,78 @@
+// Graphical view: read back what servo composited and draw it with half blocks.
+// Each terminal cell shows two pixels, the top one as the foreground of ▀ and the bottom one as the background.
+
+use std::rc::Rc;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Color,
+};
+use servo::gl;
+use servo::webrender_traits::RenderingContext;
+use surfman::GLApi;
+
+pub struct Pixels {
+    gl: Rc<dyn gl::Gl>,
+    rendering_context: RenderingContext,
+    width: i32,
+    height: i32,
+    data: Vec<u8>, // RGBA, top row first
+}
+
+impl Pixels {
+    pub fn new(rendering_context: RenderingContext, width: i32, height: i32) -> Self {
+        // Same as servo does for webrender
+        let gl = match rendering_context.connection().gl_api() {
+            GLApi::GL => unsafe { gl::GlFns::load_with(|s| rendering_context.get_proc_address(s)) },
+            GLApi::GLES => unsafe { gl::GlesFns::load_with(|s| rendering_context.get_proc_address(s)) },
+        };
+        Pixels { gl, rendering_context, width, height, data: vec![] }
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        self.data.clear();
+    }
+
+    /// Copy the frame servo just composited. Call before presenting.
+    pub fn read(&mut self) {
+        let (width, height) = (self.width, self.height);
+        if self.rendering_context.make_gl_context_current().is_err() {
+            return;
+        }
+        let framebuffer = self.rendering_context.context_surface_info()
+            .ok().flatten().map_or(0, |info| info.framebuffer_object);
+        self.gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        let data = self.gl.read_pixels(0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE);
+
+        // GL rows go bottom to top
+        let stride = width.max(0) as usize * 4;
+        self.data = data.chunks_exact(stride.max(1)).rev().flatten().copied().collect();
+    }
+
+    fn color(&self, x: u16, y: u16) -> Color {
+        let (x, y) = (x as i32, y as i32);
+        if x >= self.width || y >= self.height {
+            return Color::Reset;
+        }
+        let i = (y * self.width + x) as usize * 4;
+        match self.data.get(i..i+3) {
+            Some(&[r, g, b]) => Color::Rgb(r, g, b),
+            _ => Color::Reset,
+        }
+    }
+
+    /// Draw the last frame read, one cell per pixel column and two pixel rows per cell
+    pub fn render(&self, area: Rect, buf: &mut Buffer) {
+        for y in 0..area.height {
+            for x in 0..area.width {
+                buf[(area.x + x, area.y + y)]
+                    .set_symbol("▀")
+                    .set_fg(self.color(x, y*2))
+                    .set_bg(self.color(x, y*2 + 1));
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+// Bring a remembered element on screen so it can be clicked.
+// arguments: element index. Result is its center, in CSS pixels from the viewport corner.
+const element = window.__cuervo && window.__cuervo[arguments[0]];
+if (!element) return null;
+element.scrollIntoView({ block: "center", inline: "nearest" });
+const r = element.getBoundingClientRect();
+return [r.left + r.width / 2, r.top + r.height / 2];
@@ -0,0 +1,12 @@
+// Change a remembered checkbox, radio button or select, as if the user had.
+// arguments: element index, and the option to pick for a select. Result is null.
+const element = window.__cuervo && window.__cuervo[arguments[0]];
+if (!element) return null;
+if (element.localName === "select") {
+    element.selectedIndex = arguments[1];
+    element.dispatchEvent(new Event("input", { bubbles: true }));
+    element.dispatchEvent(new Event("change", { bubbles: true }));
+} else {
+    element.click(); // Flips it, with the events a real click would fire
+}
+return null;
@@ -0,0 +1,24 @@
+// Fetch part of a URL as the page would, to save it. arguments: url, first byte, most bytes wanted.
+// Result is { data: base64 of the bytes, total: size of the whole file or null, whole: true if that's all of it at once }.
+// Synchronous requests can't ask for binary, but the x-user-defined charset keeps one byte per character.
+const [url, start, length] = arguments;
+const request = new XMLHttpRequest();
+request.open("GET", url, false);
+request.overrideMimeType("text/plain; charset=x-user-defined");
+request.setRequestHeader("Range", "bytes=" + start + "-" + (start + length - 1));
+request.send();
+// Asking past the end means it's all here already
+if (request.status === 416) return { data: "", total: start, whole: false };
+if (request.status < 200 || request.status >= 300) throw new Error("HTTP " + request.status);
+const text = request.responseText;
+let binary = "";
+for (let i = 0; i < text.length; i += 0x8000) {
+    const bytes = Array.from(text.substring(i, i + 0x8000), c => c.charCodeAt(0) & 0xff);
+    binary += String.fromCharCode.apply(null, bytes);
+}
+const range = /\/(\d+)$/.exec(request.getResponseHeader("Content-Range") || "");
+return {
+    data: btoa(binary),
+    total: range ? Number(range[1]) : null,
+    whole: request.status !== 206,
+};
@@ -0,0 +1,13 @@
+// Read, or replace, the value of a remembered text field, or of the one with focus.
+// arguments: element index or null for the focused one, and the new value or null to only read. Result is the value.
+// Only text: checkboxes, buttons and selects have values too, which aren't for editing.
+const TEXT = ["text", "search", "url", "tel", "email", "password"];
+const element = arguments[0] === null ? document.activeElement : window.__cuervo && window.__cuervo[arguments[0]];
+if (!element || !(element.localName === "textarea" || (element.localName === "input" && TEXT.includes(element.type)))) return null;
+if (arguments[1] !== null) {
+    element.value = arguments[1];
+    // As if typed, so the page notices
+    element.dispatchEvent(new Event("input", { bubbles: true }));
+    element.dispatchEvent(new Event("change", { bubbles: true }));
+}
+return element.value;
@@ -0,0 +1,23 @@
+// Draw a remembered <img> at a given pixel size and hand back its contents.
+// arguments: element index, width, height, "png" or "rgba". Result is base64, or null if unavailable.
+const [index, width, height, format] = arguments;
+const img = window.__cuervo && window.__cuervo[index];
+if (!img || !img.complete || !img.naturalWidth) return null;
+
+const canvas = document.createElement("canvas");
+canvas.width = width;
+canvas.height = height;
+const context = canvas.getContext("2d");
+context.drawImage(img, 0, 0, width, height);
+
+try {
+    if (format === "png") return canvas.toDataURL("image/png").split(",")[1];
+    const data = context.getImageData(0, 0, width, height).data;
+    let binary = "";
+    for (let i = 0; i < data.length; i += 0x8000) {
+        binary += String.fromCharCode.apply(null, data.subarray(i, i + 0x8000));
+    }
+    return btoa(binary);
+} catch (e) {
+    return null; // Cross-origin images taint the canvas
+}
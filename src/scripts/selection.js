@@ -0,0 +1,6 @@
+// Text selected in the page, for copying. Text fields keep their own selection, apart from the page's.
+const active = document.activeElement;
+if (active && (active.tagName === "INPUT" || active.tagName === "TEXTAREA") && typeof active.selectionStart === "number") {
+    return active.value.substring(active.selectionStart, active.selectionEnd);
+}
+return String(window.getSelection() || "");
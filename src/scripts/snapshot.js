@@ -0,0 +1,114 @@
+// Page snapshot for cuervo's text renderer. Run through WebDriver.
+// Walks the document in order and returns its block structure as lists of text spans.
+// Elements we might want to talk about later are kept in window.__cuervo and named by index.
+return (function () {
+    const SKIP = new Set(["script", "style", "noscript", "template", "head", "svg", "math"]);
+    const CONTROLS = new Set(["input", "button", "select", "textarea"]);
+    const BUTTONS = new Set(["submit", "button", "reset", "image"]);
+    const CHECKS = new Set(["checkbox", "radio"]);
+    const blocks = [];
+    const elements = [];
+    let current = null;
+    let depth = 0; // List nesting
+    window.__cuervo = [];
+
+    function remember(node, kind) {
+        const r = node.getBoundingClientRect();
+        window.__cuervo.push(node);
+        elements.push({
+            kind: kind,
+            href: node.href ? String(node.href) : node.currentSrc || node.src || null, // Images give their source
+            rect: [r.left + window.scrollX, r.top + window.scrollY, r.width, r.height],
+            checked: Boolean(node.checked),
+            options: kind === "select" ? Array.from(node.options, option => option.text) : [],
+            selected: kind === "select" && node.selectedIndex >= 0 ? node.selectedIndex : null,
+        });
+        return elements.length - 1;
+    }
+
+    function open(kind, level) {
+        current = { kind: kind, level: level, spans: [] };
+        blocks.push(current);
+    }
+
+    function text(t, el) {
+        if (!current) open("p", 0);
+        current.spans.push({ text: t, el: el });
+    }
+
+    // Children of these are laid side by side: table cells, and the items of a flex row or a grid
+    function across(style) {
+        const d = style.display;
+        return d === "table-row" || d.endsWith("grid") || (d.endsWith("flex") && !style.flexDirection.startsWith("column"));
+    }
+
+    // row: node's parent lays its children side by side
+    function walk(node, el, pre, row) {
+        if (node.nodeType === Node.TEXT_NODE) {
+            const t = pre ? node.data : node.data.replace(/\s+/g, " ");
+            if (t.length) text(t, el);
+            return;
+        }
+        if (node.nodeType !== Node.ELEMENT_NODE) return;
+        const name = node.localName;
+        if (SKIP.has(name)) return;
+        const style = getComputedStyle(node);
+        if (style.display === "none" || style.visibility === "hidden") return;
+
+        if (name === "br") { text("\n", el); return; }
+        if (CONTROLS.has(name)) {
+            if (name === "input" && node.type === "hidden") return;
+            const button = name === "button" || (name === "input" && BUTTONS.has(node.type));
+            const check = name === "input" && CHECKS.has(node.type);
+            const i = remember(node, button ? "button" : check ? node.type : name);
+            if (check) { text("", i); return; } // Drawn by page.rs from checked
+            let label;
+            if (button) label = (name === "button" ? node.textContent : node.value).trim() || node.type;
+            else if (name === "select") label = (node.selectedIndex >= 0 ? node.options[node.selectedIndex].text : "") + " ▾";
+            else if (node.type === "password") label = "*".repeat(node.value.length).padEnd(12, "_");
+            else label = (node.value || "").padEnd(12, "_");
+            text("[" + label + "]", i);
+            return;
+        }
+        if (name === "img") {
+            open("img", 0);
+            current.spans.push({ text: node.alt || "", el: remember(node, "img") });
+            current = null;
+            return;
+        }
+        if (name === "a" && node.href && el === null) el = remember(node, "link");
+
+        if (style.display === "contents") {
+            for (const child of node.childNodes) walk(child, el, pre, row);
+            return;
+        }
+        let block = !style.display.startsWith("inline");
+        // Side by side in the parent's block, with a gap from the one before
+        if (block && row) {
+            if (current && current.spans.length) text(style.display === "table-cell" ? " | " : "  ", null);
+            block = false;
+        }
+        if (!block) {
+            for (const child of node.childNodes) walk(child, el, pre, across(style));
+            return;
+        }
+
+        let kind = "p", level = 0;
+        if (/^h[1-6]$/.test(name)) { kind = "h"; level = Number(name[1]); }
+        else if (name === "li") { kind = "li"; level = depth; }
+        else if (name === "pre") { kind = "pre"; }
+        else if (name === "blockquote") { kind = "quote"; }
+        else if (name === "hr") { open("hr", 0); current = null; return; }
+        const list = name === "ul" || name === "ol";
+
+        open(kind, level);
+        if (list) depth++;
+        // Text after a nested block lands in a fresh paragraph
+        for (const child of node.childNodes) walk(child, el, pre || kind === "pre", across(style));
+        if (list) depth--;
+        current = null;
+    }
+
+    walk(document.body || document.documentElement, null, false, false);
+    return { title: document.title, url: String(document.location), type: document.contentType, blocks: blocks, elements: elements };
+})();
@@ -2,9 +2,73 @@ appname = cuervo
 
 goto = Go to URL
 
+url-parse = Not an address, and no search is set up: { $url }
+url-unsupported = Can't open { $scheme }: addresses
+
+status-loading = Loading { $url }
+status-page = { $title } — { $url }
+
+tab-new = New tab
+
+popup = New tab
+popup-ask = The page in tab { $tab } wants to open a new tab. Allow it? (y/n)
+
+dialog = {appname}
+dialog-page = The page says
+dialog-alert-keys = Enter: OK
+dialog-ok-cancel-keys = Enter: OK. Esc: Cancel.
+dialog-yes-no-keys = y: Yes. n: No.
+
+menu = Menu
+select = Choose one
+menu-open-tab = Open link in new tab
+menu-copy-link = Copy link address
+menu-save-link = Save link target
+menu-view-image = View image
+menu-copy-image = Copy image address
+menu-save-image = Save image
+menu-copy-page = Copy page address
+
+copied = Copied to clipboard
+copy-failed = Couldn't copy to clipboard
+copy-nothing = Nothing selected to copy
+saving = Saving { $url }
+saved = Saved { $path }
+save-failed = Couldn't save { $url }: { $error }
+
+edit-failed = Couldn't edit: { $error }
+edit-no-field = No text field to edit
+
+files-keys = ↑↓: Move. Enter: Open or choose. ←: Up a folder. Type to filter. Esc: Cancel.
+files-keys-multiple = ↑↓: Move. Tab: Mark. Enter: Open, or choose the marked files. ←: Up a folder. Type to filter. Esc: Cancel.
+files-outside = Give the page files from outside { $root }? (y/n)
+
+save-as = Save as
+
+downloads = Downloads
+downloads-none = Nothing downloaded yet. Links to files like zips and PDFs are saved instead of opened.
+downloads-keys = j, k: Move. c: Cancel. r: Retry. x: Take off the list. Esc: Close.
+download-done = done
+download-failed = failed
+download-cancelled = cancelled
+download-tab-closed = Its tab was closed
+
+webdriver-failed = Couldn't start text view, so showing pictures: { $error }
+
 welcome = 
     {"\u000A"}Welcome to {appname}
     
     Controls:
-        g: {goto}.
+        o: {goto}.
+        f: Follow a link.
+        Tab, Shift-Tab (or n, N): Move between links and fields. Enter: Use one.
+        i: Type in the text field the page put the cursor in. Esc: Stop typing.
+        e: Edit the text field in $EDITOR (Ctrl-E while typing in it).
+        m: Menu for the link or page (or right click).
+        y, Y: Copy the page address, copy the selected text.
+        D: Downloads. Links are saved instead of opened when they end in .zip, .pdf and the like, or, in text view, when the server says they aren't a page. For others, use the m menu's Save.
+        j, k, Space, b: Scroll. g g, G: Top, bottom. In text view the status bar says how far down you are.
+        H, L: Back, forward. r: Reload.
+        t, x: New tab, close tab. J, K: Next, previous tab. F: Follow a link in a new tab.
+        v: Switch between text and picture view. CUERVO_WEBDRIVER=false turns text view off.
         q: Quit.
@@ -2,10 +2,74 @@ appname = cuervo
 
 goto = Ir a la URL
 
+url-parse = No es una dirección y no hay búsqueda configurada: { $url }
+url-unsupported = No se pueden abrir direcciones { $scheme }:
+
+status-loading = Cargando { $url }
+status-page = { $title } — { $url }
+
+tab-new = Pestaña nueva
+
+popup = Pestaña nueva
+popup-ask = La página en la pestaña { $tab } quiere abrir una pestaña nueva. ¿Permitirlo? (y/n)
+
+dialog = {appname}
+dialog-page = La página dice
+dialog-alert-keys = Enter: Aceptar
+dialog-ok-cancel-keys = Enter: Aceptar. Esc: Cancelar.
+dialog-yes-no-keys = y: Sí. n: No.
+
+menu = Menú
+select = Elige una opción
+menu-open-tab = Abrir enlace en pestaña nueva
+menu-copy-link = Copiar dirección del enlace
+menu-save-link = Guardar destino del enlace
+menu-view-image = Ver imagen
+menu-copy-image = Copiar dirección de la imagen
+menu-save-image = Guardar imagen
+menu-copy-page = Copiar dirección de la página
+
+copied = Copiado al portapapeles
+copy-failed = No se pudo copiar al portapapeles
+copy-nothing = No hay nada seleccionado para copiar
+saving = Guardando { $url }
+saved = Guardado en { $path }
+save-failed = No se pudo guardar { $url }: { $error }
+
+edit-failed = No se pudo editar: { $error }
+edit-no-field = No hay campo de texto para editar
+
+files-keys = ↑↓: Moverse. Enter: Abrir o elegir. ←: Subir una carpeta. Escribe para filtrar. Esc: Cancelar.
+files-keys-multiple = ↑↓: Moverse. Tab: Marcar. Enter: Abrir, o elegir los archivos marcados. ←: Subir una carpeta. Escribe para filtrar. Esc: Cancelar.
+files-outside = ¿Darle a la página archivos de fuera de { $root }? (y/n)
+
+save-as = Guardar como
+
+downloads = Descargas
+downloads-none = Nada descargado aún. Los enlaces a archivos como zips y PDFs se guardan en vez de abrirse.
+downloads-keys = j, k: Moverse. c: Cancelar. r: Reintentar. x: Quitar de la lista. Esc: Cerrar.
+download-done = listo
+download-failed = falló
+download-cancelled = cancelado
+download-tab-closed = Se cerró su pestaña
+
+webdriver-failed = No se pudo iniciar la vista de texto, así que se muestran imágenes: { $error }
+
 welcome = 
     {"\u000A"}Bienvenide a {appname}
     
     Controles:
-        g: {goto}.
+        o: {goto}.
+        f: Seguir un enlace.
+        Tab, Shift-Tab (o n, N): Moverse entre enlaces y campos. Enter: Usarlo.
+        i: Escribir en el campo de texto que la página enfocó. Esc: Dejar de escribir.
+        e: Editar el campo de texto en $EDITOR (Ctrl-E mientras escribes en él).
+        m: Menú del enlace o la página (o clic derecho).
+        y, Y: Copiar la dirección de la página, copiar el texto seleccionado.
+        D: Descargas. Los enlaces se guardan en vez de abrirse cuando terminan en .zip, .pdf y similares o, en la vista de texto, cuando el servidor dice que no son una página. Para los demás, usa Guardar en el menú m.
+        j, k, Espacio, b: Desplazarse. g g, G: Inicio, final. En la vista de texto, la barra de estado dice cuánto has bajado.
+        H, L: Atrás, adelante. r: Recargar.
+        t, x: Pestaña nueva, cerrar pestaña. J, K: Pestaña siguiente, anterior. F: Seguir un enlace en una pestaña nueva.
+        v: Cambiar entre vista de texto y de imagen. CUERVO_WEBDRIVER=false desactiva la vista de texto.
         q: Salir.
 
@@ -4,9 +4,73 @@ _goto = tawa URL
 
 goto = o {_goto}
 
+url-parse = nimi ni li nimi tawa ala, li ken ala alasa: { $url }
+url-unsupported = nimi lipu { $scheme }: li ken ala open
+
+status-loading = mi open e { $url }
+status-page = { $title } — { $url }
+
+tab-new = lipu sin
+
+popup = lipu sin
+popup-ask = lipu nanpa { $tab } li wile open e lipu sin. ni li pona anu seme? (y/n)
+
+dialog = {appname}
+dialog-page = lipu li toki e ni
+dialog-alert-keys = Enter: pona
+dialog-ok-cancel-keys = Enter: pona. Esc: ala.
+dialog-yes-no-keys = y: lon. n: ala.
+
+menu = ijo ken
+select = o wile e wan
+menu-open-tab = o open e lipu ante lon lipu sin
+menu-copy-link = o jo e nimi tawa
+menu-save-link = o awen e lipu ante
+menu-view-image = o lukin e sitelen
+menu-copy-image = o jo e nimi sitelen
+menu-save-image = o awen e sitelen
+menu-copy-page = o jo e nimi lipu
+
+copied = mi jo e ni
+copy-failed = mi ken ala jo e ni
+copy-nothing = sina wile jo e ala
+saving = mi awen e { $url }
+saved = mi awen lon { $path }
+save-failed = mi ken ala awen e { $url }: { $error }
+
+edit-failed = mi ken ala ante: { $error }
+edit-no-field = lipu sitelen li lon ala
+
+files-keys = ↑↓: o tawa. Enter: o open anu o wile. ←: o tawa poki sewi. o sitelen tawa alasa. Esc: o pini.
+files-keys-multiple = ↑↓: o tawa. Tab: o sitelen e ni. Enter: o open, anu o wile e ijo sitelen. ←: o tawa poki sewi. o sitelen tawa alasa. Esc: o pini.
+files-outside = lipu li wile e ijo lon poki ante { $root }. ni li pona anu seme? (y/n)
+
+save-as = o awen lon
+
+downloads = ijo awen
+downloads-none = ijo awen li lon ala. lipu zip en lipu PDF li awen li open ala.
+downloads-keys = j, k: o tawa. c: o pini. r: o pali sin. x: o weka tan lipu. Esc: o pini e lukin.
+download-done = pini
+download-failed = pakala
+download-cancelled = weka
+download-tab-closed = lipu ona li weka
+
+webdriver-failed = lukin sitelen taso. lukin nimi li ken ala: { $error }
+
 welcome =
     {"\u000A"}o kama pona tawa {appname}
 
     ilo kepeken:
-        g: {_goto}.
+        o: {_goto}.
+        f: o tawa lipu ante.
+        Tab, Shift-Tab (anu n, N): o tawa ijo ante. Enter: o kepeken ijo.
+        i: o sitelen lon ijo sitelen pi lipu ni. Esc: o pini e sitelen.
+        e: o ante e ijo sitelen kepeken ilo $EDITOR (Ctrl-E lon tenpo sitelen).
+        m: o lukin e ijo ken (anu nena soweli pi poka teja).
+        y, Y: o jo e nimi lipu, o jo e toki wile.
+        D: ijo awen. nimi tawa li pini kepeken .zip anu .pdf la, mi awen e ona. lukin sitelen nimi la, ilo lipu li toki e ni: ona li lipu ala la, mi awen e ona. ante la, o kepeken m li o awen.
+        j, k, Space, b: o tawa sewi anu anpa. g g, G: o tawa open, o tawa pini. lon lukin nimi la linja anpa li toki e ni: sina lon seme.
+        H, L: o tawa lipu pini, o tawa lipu kama. r: o open sin.
+        t, x: o open e lipu sin, o weka e lipu. J, K: o tawa lipu kama, o tawa lipu pini. F: o open e lipu ante lon lipu sin.
+        v: ante e nasin lukin. CUERVO_WEBDRIVER=false la lukin nimi li lon ala.
         q: tawa weka.
@@ -0,0 +1,61 @@
+// Tabs: one servo webview (top-level browsing context) each, and what we know about its page.
+
+use servo::servo_url::ServoUrl;
+use servo::TopLevelBrowsingContextId;
+
+use crate::page::{Lines, Page};
+use crate::BarState;
+
+pub struct Tab {
+    pub id: TopLevelBrowsingContextId,
+    pub opened: bool, // Has servo been asked to create the webview yet
+    pub bar_state: BarState,
+    pub page: Option<Page>, // Last snapshot of the loaded page
+    pub laid: Option<Lines>, // page at the current terminal width
+    pub stale: bool, // Page loaded while in the background, so page needs a new snapshot
+    pub focus: Option<usize>, // Element under the focus cursor
+    pub scroll: usize, // First row of laid shown
+    pub history: Vec<ServoUrl>, // Session history, as last reported by servo
+    pub history_index: usize, // Current entry in history
+    pub loading: Option<ServoUrl>, // Where servo is navigating to
+    pub title: Option<String>, // As servo reports it
+    pub opener: Option<TopLevelBrowsingContextId>, // Tab whose page opened this one
+}
+
+impl Tab {
+    pub fn new(id: TopLevelBrowsingContextId) -> Self {
+        Tab {
+            id, opened: false, bar_state: BarState::None, page: None, laid: None, stale: false, focus: None, scroll: 0,
+            history: vec![], history_index: 0, loading: None, title: None, opener: None,
+        }
+    }
+
+    /// What's loading, else what's loaded
+    pub fn url(&self) -> Option<&ServoUrl> {
+        self.loading.as_ref().or_else(|| self.history.get(self.history_index))
+    }
+
+    /// Is there history to go back (or forward) to?
+    pub fn can_go(&self, forward: bool) -> bool {
+        if forward { self.history_index + 1 < self.history.len() } else { self.history_index > 0 }
+    }
+
+    /// Take a new snapshot. The same page again (after a resize, say) keeps its place.
+    pub fn set_page(&mut self, page: Option<Page>) {
+        let same = page.as_ref().and_then(|p| p.url.as_ref()) == self.page.as_ref().and_then(|p| p.url.as_ref());
+        if !same {
+            self.focus = None;
+            self.scroll = 0;
+        }
+        self.page = page;
+        self.laid = None;
+        self.stale = false;
+    }
+
+    /// What to call it in the tab strip
+    pub fn label(&self) -> &str {
+        self.title.as_deref().filter(|title| !title.is_empty())
+            .or_else(|| self.url().map(ServoUrl::as_str))
+            .unwrap_or("")
+    }
+}
@@ -0,0 +1,86 @@
+// Asking the terminal things. Replies come back as terminal input, so they're read here directly,
+// while nothing else is reading: before the input thread starts, or while it's paused.
+// Every terminal answers DA1 (primary device attributes), so it goes last and its reply ends the wait.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+const WAIT:Duration = Duration::from_millis(500); // For a terminal that doesn't answer DA1
+const DA1:&str = "\x1b[c";
+
+/// What the terminal said about itself
+#[derive(Debug, Default)]
+pub struct Answers {
+    pub kitty: bool, // Kitty graphics protocol
+    pub sixel: bool,
+    pub cell: Option<(u16, u16)>, // Size of a cell in screen pixels
+}
+
+/// Ask about images and cell size. Call in raw mode, before the input thread starts
+pub fn ask() -> Answers {
+    // Kitty: query (a=q) a 1x1 image, which it checks but doesn't show. CSI 16t: cell size
+    let reply = query("\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[16t").unwrap_or_default();
+    Answers {
+        kitty: reply.contains("\x1b_Gi=31;OK"),
+        // Attribute 4 is sixel graphics
+        sixel: csi(&reply, "\x1b[?", 'c').is_some_and(|params| params.split(';').any(|param| param == "4")),
+        cell: csi(&reply, "\x1b[6;", 't').and_then(|params| {
+            let (height, width) = params.split_once(';')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        }).filter(|&(width, height)| width > 0 && height > 0),
+    }
+}
+
+/// The terminal's clipboard, by OSC 52. None if it won't say. Call with the input thread paused
+pub fn clipboard() -> Option<String> {
+    let reply = query("\x1b]52;c;?\x07").ok()?;
+    let rest = &reply[reply.find("\x1b]52;")? + 5..];
+    let (_, data) = rest.split_once(';')?; // After which clipboard
+    let data = &data[..data.find(['\x07', '\x1b'])?]; // BEL or ST ends it
+    String::from_utf8(BASE64.decode(data).ok()?).ok()
+}
+
+// Send ask, then DA1, and collect everything up to DA1's reply
+fn query(ask: &str) -> io::Result<String> {
+    let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+    write!(tty, "{ask}{DA1}")?;
+    tty.flush()?;
+
+    let deadline = Instant::now() + WAIT;
+    let mut reply = vec![];
+    let mut buf = [0; 256];
+    while csi(&String::from_utf8_lossy(&reply), "\x1b[?", 'c').is_none() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() || !readable(&tty, left) {
+            break;
+        }
+        match tty.read(&mut buf)? {
+            0 => break,
+            n => reply.extend_from_slice(&buf[..n]),
+        }
+    }
+    Ok(String::from_utf8_lossy(&reply).into_owned())
+}
+
+// Parameters of the first reply that starts with start and ends with end, if there's one whole
+fn csi<'a>(reply: &'a str, start: &str, end: char) -> Option<&'a str> {
+    let rest = &reply[reply.find(start)? + start.len()..];
+    let params = &rest[..rest.find(end)?];
+    params.chars().all(|c| c.is_ascii_digit() || c == ';').then_some(params)
+}
+
+#[cfg(unix)]
+fn readable(tty: &File, timeout: Duration) -> bool {
+    use std::os::fd::AsRawFd;
+    let mut poll = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+    // Safe: one pollfd, and it's ours
+    unsafe { libc::poll(&mut poll, 1, timeout.as_millis().min(i32::MAX as u128) as libc::c_int) > 0 }
+}
+
+#[cfg(not(unix))]
+fn readable(_tty: &File, _timeout: Duration) -> bool {
+    false
+}
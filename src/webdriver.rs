@@ -0,0 +1,220 @@
+// Minimal WebDriver client, used to run scripts inside the page.
+// Servo doesn't let an embedder see the DOM or layout, but it will run a WebDriver server
+// if asked to, so we start one and talk to it over loopback from a worker thread.
+// That server binds every interface (0.0.0.0), which would let anyone on the network run scripts in the user's
+// pages. Servo takes no address for it, so cuervo's own bind() stands in front of libc's and moves that one
+// listener to loopback. Sessions only start once it has; where that can't be done (not unix), it isn't started.
+// Servo's server only knows one window, whichever webview was focused when the session began,
+// so scripts for another tab get a new session. Focus the tab before running scripts in it.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use servo::embedder_traits::EventLoopWaker;
+use servo::TopLevelBrowsingContextId;
+
+const CONNECT_TRIES:u32 = 50;
+const CONNECT_WAIT:Duration = Duration::from_millis(100);
+
+static PORT:AtomicU16 = AtomicU16::new(0); // Servo's WebDriver port, once picked
+static LOOPBACK:AtomicBool = AtomicBool::new(false); // Servo's server was moved to loopback
+static EXPOSED:AtomicBool = AtomicBool::new(false); // Servo's server answered, but wasn't moved
+
+/// Why a script was run; handed back with the result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purpose { Snapshot, Image(usize), Activate, Save(usize), Selection, ReadField(Option<usize>), WriteField } // Save: id of a download. ReadField: None for the focused field
+
+/// Pick a free port and ask servo to start its WebDriver server there. Call right before servo::Servo::new,
+/// since the port is free for anyone until servo takes it. Client::session checks it was servo that did.
+pub fn enable() -> io::Result<u16> {
+    if cfg!(not(unix)) {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "can't keep servo's WebDriver server off the network here"));
+    }
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    PORT.store(port, Ordering::SeqCst);
+    let mut opts = servo::config::opts::get().clone();
+    opts.webdriver_port = Some(port);
+    servo::config::opts::set_options(opts);
+    Ok(port)
+}
+
+// Stands in for libc's bind(), for servo's code as well as ours. An IPv4 bind to every interface on the WebDriver
+// port becomes a bind to 127.0.0.1; anything else is passed on as is.
+#[cfg(unix)]
+#[no_mangle]
+unsafe extern "C" fn bind(fd: libc::c_int, addr: *const libc::sockaddr, len: libc::socklen_t) -> libc::c_int {
+    type Bind = unsafe extern "C" fn(libc::c_int, *const libc::sockaddr, libc::socklen_t) -> libc::c_int;
+    // The next bind after ours is libc's
+    let real = libc::dlsym(libc::RTLD_NEXT, c"bind".as_ptr());
+    if real.is_null() {
+        return -1; // No libc bind to call; can't happen where there's a libc
+    }
+    let real:Bind = std::mem::transmute(real);
+
+    let port = PORT.load(Ordering::SeqCst);
+    if port != 0 && !addr.is_null() && (*addr).sa_family == libc::AF_INET as libc::sa_family_t
+        && len as usize >= std::mem::size_of::<libc::sockaddr_in>()
+    {
+        let mut inet = *(addr as *const libc::sockaddr_in);
+        if u16::from_be(inet.sin_port) == port && inet.sin_addr.s_addr == libc::INADDR_ANY {
+            inet.sin_addr.s_addr = libc::INADDR_LOOPBACK.to_be();
+            let result = real(fd, &inet as *const libc::sockaddr_in as *const libc::sockaddr, len);
+            if result == 0 {
+                LOOPBACK.store(true, Ordering::SeqCst);
+            }
+            return result;
+        }
+    }
+    real(fd, addr, len)
+}
+
+/// Did servo's server turn out to listen on every interface? Then scripts should be turned off
+pub fn exposed() -> bool {
+    EXPOSED.load(Ordering::SeqCst)
+}
+
+struct Client {
+    port: u16,
+    session: Option<String>,
+    tab: Option<TopLevelBrowsingContextId>, // Tab the session was made for
+}
+
+impl Client {
+    fn request(&self, method: &str, path: &str, body: &Value) -> Result<Value, String> {
+        let body = body.to_string();
+
+        // The server starts up on its own schedule, so the first request may have to wait for it
+        let mut tries = 0;
+        let mut stream = loop {
+            match TcpStream::connect(("127.0.0.1", self.port)) {
+                Ok(stream) => break stream,
+                Err(_) if tries < CONNECT_TRIES => { tries += 1; std::thread::sleep(CONNECT_WAIT); },
+                Err(e) => return Err(format!("WebDriver connect: {e}")),
+            }
+        };
+
+        write!(stream,
+            "{method} {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        ).map_err(|e| format!("WebDriver write: {e}"))?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(|e| format!("WebDriver read: {e}"))?;
+
+        let (head, body) = response.split_once("\r\n\r\n").ok_or("WebDriver sent a malformed response")?;
+        let value = serde_json::from_str::<Value>(body)
+            .map_err(|e| format!("WebDriver sent bad JSON: {e}"))?
+            .get_mut("value").map(Value::take).unwrap_or(Value::Null);
+
+        // Errors come back with a non-200 status and an error object in "value"
+        if head.starts_with("HTTP/1.1 200") {
+            Ok(value)
+        } else {
+            Err(format!("WebDriver: {} ({})", value["error"].as_str().unwrap_or("?"), value["message"].as_str().unwrap_or("")))
+        }
+    }
+
+    fn session(&mut self) -> Result<String, String> {
+        if let Some(session) = &self.session {
+            return Ok(session.clone());
+        }
+        // The server is up once it answers, so its bind has happened by now
+        let value = self.request("POST", "/session", &json!({"capabilities": {}}))?;
+        if !LOOPBACK.load(Ordering::SeqCst) {
+            EXPOSED.store(true, Ordering::SeqCst);
+            return Err("WebDriver server isn't limited to loopback".to_string());
+        }
+        // Something else got the port first; don't hand it our scripts
+        if value["capabilities"]["browserName"] != "servo" {
+            return Err("WebDriver port answered by something other than servo".to_string());
+        }
+        let session = value["sessionId"].as_str().ok_or("WebDriver didn't give a session")?.to_string();
+        self.session = Some(session.clone());
+        Ok(session)
+    }
+
+    fn end_session(&mut self) {
+        if let Some(session) = self.session.take() {
+            let _ = self.request("DELETE", &format!("/session/{session}"), &json!({}));
+        }
+    }
+
+    fn execute(&mut self, tab: TopLevelBrowsingContextId, script: &str, args: Vec<Value>) -> Result<Value, String> {
+        if self.tab != Some(tab) {
+            self.end_session();
+            self.tab = Some(tab);
+        }
+        let session = self.session()?;
+        self.request("POST", &format!("/session/{session}/execute/sync"), &json!({"script": script, "args": args}))
+    }
+}
+
+type Job = (TopLevelBrowsingContextId, Purpose, &'static str, Vec<Value>);
+
+/// Runs scripts in the page, one at a time, off the main thread.
+pub struct Scripts {
+    jobs: Option<Sender<Job>>, // None if WebDriver is off
+    results: Receiver<(TopLevelBrowsingContextId, Purpose, Result<Value, String>)>,
+}
+
+impl Scripts {
+    /// Waker is woken whenever a result is ready. Without a port, scripts are dropped unrun
+    pub fn new(port: Option<u16>, waker: Box<dyn EventLoopWaker>) -> Self {
+        let (result_sender, results) = channel();
+        let Some(port) = port else { return Scripts { jobs: None, results } };
+        let (jobs, job_receiver) = channel::<Job>();
+        std::thread::spawn(move || {
+            let mut client = Client { port, session: None, tab: None };
+            for (tab, purpose, script, args) in job_receiver {
+                if result_sender.send((tab, purpose, client.execute(tab, script, args))).is_err() {
+                    break; // App is gone
+                }
+                waker.wake();
+            }
+        });
+        Scripts { jobs: Some(jobs), results }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.jobs.is_some()
+    }
+
+    /// Stop running scripts, for good
+    pub fn disable(&mut self) {
+        self.jobs = None;
+    }
+
+    /// Queue a script for a tab. Script is a function body; `arguments` holds args.
+    pub fn run(&self, tab: TopLevelBrowsingContextId, purpose: Purpose, script: &'static str, args: Vec<Value>) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send((tab, purpose, script, args));
+        }
+    }
+
+    /// Next finished script, if any.
+    pub fn try_recv(&self) -> Option<(TopLevelBrowsingContextId, Purpose, Result<Value, String>)> {
+        self.results.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn webdriver_port_kept_to_loopback() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        PORT.store(port, Ordering::SeqCst);
+        let listener = TcpListener::bind(("0.0.0.0", port)).unwrap();
+        assert_eq!(listener.local_addr().unwrap(), ([127, 0, 0, 1], port).into());
+        assert!(LOOPBACK.load(Ordering::SeqCst));
+
+        // Other ports are left alone
+        let other = TcpListener::bind("0.0.0.0:0").unwrap();
+        assert!(other.local_addr().unwrap().ip().is_unspecified());
+    }
+}